@@ -12,14 +12,15 @@ fn main() {
     let engine = Engine::new(
         EngineBackend::Auto { offscreen: false },
         EngineContext::Internal,
-    );
+    )
+    .unwrap();
     Engine::auto_load_plugins();
 
     let window = engine.window();
-    window.set_title("Hello from Rust");
+    window.set_title("Hello from Rust").unwrap();
 
     let options = engine.options();
-    options.set_bool("render.grid.enable", true);
+    options.render().grid().set_enable(true).unwrap();
 
     let scene = engine.scene();
     scene.add(mesh_path).unwrap();
@@ -26,6 +26,7 @@ fn main() {
     let bindings = bindgen::Builder::default()
         .header("vendor/libf3d/c/engine_c_api.h")
         .header("vendor/libf3d/c/interactor_c_api.h")
+        .header("vendor/libf3d/c/log_c_api.h")
         .header("vendor/libf3d/c/options_c_api.h")
         .header("vendor/libf3d/c/scene_c_api.h")
         .header("vendor/libf3d/c/window_c_api.h")
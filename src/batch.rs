@@ -0,0 +1,321 @@
+//! Headless batch rendering of thumbnails.
+//!
+//! A [`BatchRenderer`] owns a single offscreen engine and reuses it for every
+//! input, clearing the scene between renders.
+
+use crate::camera::Camera;
+use crate::engine::{Engine, EngineBackend, EngineContext};
+use crate::error::Result;
+use crate::image::ImageFormat;
+use crate::options::OptionsMap;
+use crate::types::{F3DColor, F3DMesh};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraPreset {
+    /// Camera as positioned by libf3d after loading.
+    Default,
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Isometric,
+    /// Rotation in degrees around the view up vector, then around the right vector.
+    Orbit {
+        azimuth: f64,
+        elevation: f64,
+    },
+}
+
+impl CameraPreset {
+    fn apply(&self, camera: &Camera) {
+        let (azimuth, elevation) = match *self {
+            CameraPreset::Default => return,
+            CameraPreset::Front => (0.0, 0.0),
+            CameraPreset::Back => (180.0, 0.0),
+            CameraPreset::Left => (-90.0, 0.0),
+            CameraPreset::Right => (90.0, 0.0),
+            CameraPreset::Top => (0.0, 89.0),
+            CameraPreset::Bottom => (0.0, -89.0),
+            CameraPreset::Isometric => (45.0, 35.264),
+            CameraPreset::Orbit { azimuth, elevation } => (azimuth, elevation),
+        };
+        camera.camera_azimuth(azimuth);
+        camera.camera_elevation(elevation);
+        camera.reset_to_bounds(0.9);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchSettings {
+    pub output_dir: PathBuf,
+    pub width: i32,
+    pub height: i32,
+    pub format: ImageFormat,
+    pub camera: CameraPreset,
+    /// Overrides `render.background.color` when set.
+    pub background: Option<F3DColor>,
+    /// Renders with a transparent background.
+    pub no_background: bool,
+    /// Options applied before every render, see [`Options::to_map`](crate::options::Options::to_map).
+    pub options: OptionsMap,
+}
+
+impl BatchSettings {
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            width: 256,
+            height: 256,
+            format: ImageFormat::PNG,
+            camera: CameraPreset::Default,
+            background: None,
+            no_background: false,
+            options: OptionsMap::new(),
+        }
+    }
+}
+
+pub enum BatchInput {
+    File(PathBuf),
+    /// In-memory mesh; `name` is used as the output file stem.
+    Mesh {
+        name: String,
+        mesh: F3DMesh,
+    },
+}
+
+impl BatchInput {
+    /// Name used in reports and as the output file stem.
+    pub fn name(&self) -> String {
+        match self {
+            BatchInput::File(path) => path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.to_string_lossy().into_owned()),
+            BatchInput::Mesh { name, .. } => name.clone(),
+        }
+    }
+}
+
+impl From<PathBuf> for BatchInput {
+    fn from(path: PathBuf) -> Self {
+        BatchInput::File(path)
+    }
+}
+
+impl From<&Path> for BatchInput {
+    fn from(path: &Path) -> Self {
+        BatchInput::File(path.to_path_buf())
+    }
+}
+
+impl From<&str> for BatchInput {
+    fn from(path: &str) -> Self {
+        BatchInput::File(PathBuf::from(path))
+    }
+}
+
+#[derive(Debug)]
+pub struct BatchResult {
+    pub name: String,
+    pub output: PathBuf,
+    pub outcome: Result<()>,
+    pub elapsed: Duration,
+}
+
+impl BatchResult {
+    pub fn is_success(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub results: Vec<BatchResult>,
+    pub elapsed: Duration,
+}
+
+impl BatchReport {
+    pub fn succeeded(&self) -> impl Iterator<Item = &BatchResult> {
+        self.results.iter().filter(|result| result.is_success())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &BatchResult> {
+        self.results.iter().filter(|result| !result.is_success())
+    }
+}
+
+pub struct BatchRenderer {
+    engine: Engine,
+    settings: BatchSettings,
+}
+
+impl BatchRenderer {
+    pub fn new(settings: BatchSettings) -> Result<Self> {
+        let engine = Engine::new(
+            EngineBackend::Auto { offscreen: true },
+            EngineContext::Internal,
+        )?;
+        Ok(Self::with_engine(engine, settings))
+    }
+
+    /// Uses an already configured engine, which should be offscreen.
+    pub fn with_engine(engine: Engine, settings: BatchSettings) -> Self {
+        Self { engine, settings }
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn settings(&self) -> &BatchSettings {
+        &self.settings
+    }
+
+    /// Renders every input in order, continuing past failures.
+    pub fn run<I>(&self, inputs: I) -> BatchReport
+    where
+        I: IntoIterator,
+        I::Item: Into<BatchInput>,
+    {
+        let start = Instant::now();
+        let results = inputs
+            .into_iter()
+            .map(|input| self.render(&input.into()))
+            .collect();
+        BatchReport {
+            results,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    pub fn render(&self, input: &BatchInput) -> BatchResult {
+        let start = Instant::now();
+        let name = input.name();
+        let output = self
+            .settings
+            .output_dir
+            .join(format!("{name}.{}", extension(self.settings.format)));
+        let outcome = self.render_to(input, &output);
+        BatchResult {
+            name,
+            output,
+            outcome,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    fn render_to(&self, input: &BatchInput, output: &Path) -> Result<()> {
+        let settings = &self.settings;
+        let options = self.engine.options();
+        options.apply_map(&settings.options)?;
+        if let Some(color) = settings.background {
+            options.render().background().set_color(color)?;
+        }
+
+        let window = self.engine.window();
+        window.set_size(settings.width, settings.height);
+
+        let scene = self.engine.scene();
+        scene.clear();
+        match input {
+            BatchInput::File(path) => scene.add(&path.to_string_lossy())?,
+            BatchInput::Mesh { mesh, .. } => scene.add_mesh(mesh)?,
+        }
+
+        settings.camera.apply(&window.camera());
+
+        let image = window.render_to_image(settings.no_background)?;
+        std::fs::create_dir_all(&settings.output_dir)?;
+        image.save(&output.to_string_lossy(), settings.format)
+    }
+}
+
+fn extension(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::PNG => "png",
+        ImageFormat::JPEG => "jpg",
+        ImageFormat::TIF => "tif",
+        ImageFormat::DMP => "dmp",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn triangle() -> F3DMesh {
+        F3DMesh::builder()
+            .points(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]])
+            .normals(&[[0.0, 0.0, 1.0]; 3])
+            .texcoords(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]])
+            .triangles(&[[0, 1, 2]])
+            .build()
+            .unwrap()
+    }
+
+    fn output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("f3d-batch-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn renders_meshes_and_reports_failures() {
+        let dir = output_dir("report");
+        let mut settings = BatchSettings::new(&dir);
+        settings.width = 64;
+        settings.height = 48;
+        settings.camera = CameraPreset::Isometric;
+        settings.background = Some(F3DColor::new(0.1, 0.2, 0.3));
+        let renderer = BatchRenderer::new(settings).unwrap();
+
+        let report = renderer.run(vec![
+            BatchInput::Mesh {
+                name: "triangle".to_string(),
+                mesh: triangle(),
+            },
+            BatchInput::File(dir.join("missing.unknown-format")),
+        ]);
+
+        assert_eq!(report.results.len(), 2);
+        let rendered = &report.results[0];
+        assert!(rendered.is_success(), "{:?}", rendered.outcome);
+        assert_eq!(rendered.output, dir.join("triangle.png"));
+        assert!(rendered.output.exists());
+
+        let missing = &report.results[1];
+        assert!(matches!(missing.outcome, Err(Error::UnsupportedFile(_))));
+        assert!(!missing.output.exists());
+
+        assert_eq!(report.succeeded().count(), 1);
+        assert_eq!(report.failed().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reuses_engine_across_renders() {
+        let dir = output_dir("reuse");
+        let thumbnails = dir.join("thumbnails");
+        let mut settings = BatchSettings::new(&thumbnails);
+        settings.format = ImageFormat::JPEG;
+        let renderer = BatchRenderer::new(settings).unwrap();
+
+        let report = renderer.run((0..3).map(|i| BatchInput::Mesh {
+            name: format!("mesh{i}"),
+            mesh: triangle(),
+        }));
+
+        assert_eq!(report.succeeded().count(), 3);
+        for i in 0..3 {
+            assert!(thumbnails.join(format!("mesh{i}.jpg")).exists());
+        }
+        assert_eq!(renderer.engine().window().width(), 256);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+//! Command-line viewer and renderer mirroring the f3d application options.
+
+use clap::Parser;
+use f3d::camera::Camera;
+use f3d::engine::{Engine, EngineBackend, EngineContext};
+use f3d::image::{Image, ImageFormat};
+use f3d::options::Options;
+use f3d::types::{F3DPoint3, F3DVector3};
+use f3d::{Error, Result};
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "f3d-rs", version, about = "Render 3D files with libf3d")]
+struct Cli {
+    /// Files to load.
+    inputs: Vec<String>,
+
+    /// Render offscreen into this image instead of opening a window.
+    #[arg(long)]
+    output: Option<String>,
+
+    /// Window or image size as `width,height`.
+    #[arg(long, value_parser = parse_resolution)]
+    resolution: Option<(i32, i32)>,
+
+    /// Up direction, e.g. `+Z`.
+    #[arg(long)]
+    up: Option<String>,
+
+    /// Show the grid.
+    #[arg(long)]
+    grid: bool,
+
+    /// Render with a transparent background.
+    #[arg(long)]
+    no_background: bool,
+
+    /// Animation time to load.
+    #[arg(long)]
+    animation_time: Option<f64>,
+
+    #[arg(long, value_parser = parse_vector)]
+    camera_position: Option<[f64; 3]>,
+
+    #[arg(long, value_parser = parse_vector)]
+    camera_focal_point: Option<[f64; 3]>,
+
+    #[arg(long, value_parser = parse_vector)]
+    camera_view_up: Option<[f64; 3]>,
+
+    /// Direction the camera looks at, keeping its distance to the focal point.
+    #[arg(long, value_parser = parse_vector)]
+    camera_direction: Option<[f64; 3]>,
+
+    #[arg(long)]
+    camera_view_angle: Option<f64>,
+
+    #[arg(long)]
+    camera_azimuth_angle: Option<f64>,
+
+    #[arg(long)]
+    camera_elevation_angle: Option<f64>,
+
+    #[arg(long)]
+    camera_zoom_factor: Option<f64>,
+
+    #[arg(long)]
+    camera_orthographic: Option<bool>,
+
+    /// Compare the rendering against this image and fail above the threshold.
+    #[arg(long)]
+    reference: Option<String>,
+
+    #[arg(long, default_value_t = 0.04)]
+    reference_threshold: f64,
+
+    /// Set a libf3d option, e.g. `-D render.effect.tone_mapping=true`.
+    #[arg(short = 'D', long = "define", value_name = "KEY=VALUE")]
+    defines: Vec<String>,
+
+    /// Reset a libf3d option to its default value.
+    #[arg(short = 'R', long = "reset", value_name = "KEY")]
+    resets: Vec<String>,
+
+    /// Play an interaction log before rendering.
+    #[arg(long)]
+    interaction_test_play: Option<String>,
+}
+
+fn parse_resolution(value: &str) -> std::result::Result<(i32, i32), String> {
+    let (width, height) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected `width,height`, got `{value}`"))?;
+    let parse = |v: &str| v.trim().parse::<i32>().map_err(|err| err.to_string());
+    Ok((parse(width)?, parse(height)?))
+}
+
+fn parse_vector(value: &str) -> std::result::Result<[f64; 3], String> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().parse::<f64>().map_err(|err| err.to_string()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    values
+        .try_into()
+        .map_err(|_| format!("expected `x,y,z`, got `{value}`"))
+}
+
+fn image_format(path: &str) -> ImageFormat {
+    let extension = Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("jpg" | "jpeg") => ImageFormat::JPEG,
+        Some("tif" | "tiff") => ImageFormat::TIF,
+        Some("dmp") => ImageFormat::DMP,
+        _ => ImageFormat::PNG,
+    }
+}
+
+fn apply_options(cli: &Cli, options: &Options) -> Result<()> {
+    if cli.grid {
+        options.render().grid().set_enable(true)?;
+    }
+    if let Some(up) = &cli.up {
+        options.set_string_representation("scene.up_direction", up)?;
+    }
+    if let Some(time) = cli.animation_time {
+        options.scene().animation().set_time(time)?;
+    }
+    if let Some(orthographic) = cli.camera_orthographic {
+        options.scene().camera().set_orthographic(orthographic)?;
+    }
+    for define in &cli.defines {
+        let (key, value) = define
+            .split_once('=')
+            .ok_or_else(|| Error::InvalidOptionKey(define.clone()))?;
+        options.set_string_representation(key.trim(), value.trim())?;
+    }
+    for key in &cli.resets {
+        options.reset(key)?;
+    }
+    Ok(())
+}
+
+fn apply_camera(cli: &Cli, camera: &Camera) {
+    if let Some([x, y, z]) = cli.camera_position {
+        camera.set_position(F3DPoint3::new(x, y, z));
+    }
+    if let Some([x, y, z]) = cli.camera_focal_point {
+        camera.set_focal_point(F3DPoint3::new(x, y, z));
+    }
+    if let Some([x, y, z]) = cli.camera_view_up {
+        camera.set_view_up(F3DVector3::new(x, y, z));
+    }
+    if let Some(direction) = cli.camera_direction {
+        let focal = camera.get_focal_point().data;
+        let position = camera.get_position().data;
+        let distance = (0..3)
+            .map(|i| (focal[i] - position[i]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        let length = direction.iter().map(|v| v * v).sum::<f64>().sqrt();
+        if length > 0.0 {
+            let position = [0, 1, 2].map(|i| focal[i] - direction[i] / length * distance);
+            camera.set_position(F3DPoint3::new(position[0], position[1], position[2]));
+        }
+    }
+    if let Some(angle) = cli.camera_view_angle {
+        camera.set_view_angle(angle);
+    }
+    if let Some(angle) = cli.camera_azimuth_angle {
+        camera.camera_azimuth(angle);
+    }
+    if let Some(angle) = cli.camera_elevation_angle {
+        camera.camera_elevation(angle);
+    }
+    if let Some(factor) = cli.camera_zoom_factor {
+        camera.camera_zoom(factor);
+    }
+}
+
+fn run(cli: &Cli) -> Result<bool> {
+    let offscreen = cli.output.is_some() || cli.reference.is_some();
+    let engine = Engine::new(EngineBackend::Auto { offscreen }, EngineContext::Internal)?;
+    Engine::auto_load_plugins();
+
+    apply_options(cli, &engine.options())?;
+
+    let window = engine.window();
+    if let Some((width, height)) = cli.resolution {
+        window.set_size(width, height);
+    }
+
+    let scene = engine.scene();
+    if !cli.inputs.is_empty() {
+        scene.add_multiple(cli.inputs.iter().map(String::as_str).collect())?;
+    }
+    if let Some(time) = cli.animation_time {
+        scene.load_animation_time(time);
+    }
+
+    apply_camera(cli, &window.camera());
+
+    let interactor = engine.interactor();
+    if let Some(log) = &cli.interaction_test_play {
+        interactor.play_interaction(log, 1.0 / 30.0)?;
+    }
+
+    if !offscreen {
+        interactor.start(1.0 / 30.0);
+        return Ok(true);
+    }
+
+    let image = window.render_to_image(cli.no_background)?;
+    if let Some(output) = &cli.output {
+        image.save(output, image_format(output))?;
+    }
+
+    if let Some(reference) = &cli.reference {
+        let reference_image = Image::new_path(reference)?;
+        let error = image.compare(&reference_image);
+        if error > cli.reference_threshold {
+            eprintln!(
+                "Image comparison failed: error {error} is above threshold {}",
+                cli.reference_threshold
+            );
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("f3d-rs: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::sys::*;
 use crate::types;
+use std::marker::PhantomData;
 use std::ptr::NonNull;
 
 pub struct CameraState {
@@ -9,14 +10,17 @@ pub struct CameraState {
     view_angle: f64,
 }
 
-pub struct Camera {
+/// Handle to the camera of an engine's window, valid for the engine's lifetime.
+pub struct Camera<'a> {
     ptr: NonNull<f3d_camera_t>,
+    _owner: PhantomData<&'a ()>,
 }
 
-impl Camera {
+impl<'a> Camera<'a> {
     pub(crate) unsafe fn from_raw(ptr: *mut f3d_camera_t) -> Self {
         Self {
             ptr: NonNull::new(ptr).expect("null f3d_camera_t"),
+            _owner: PhantomData,
         }
     }
 
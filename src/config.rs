@@ -0,0 +1,554 @@
+//! Reader for f3d application configuration files (`f3d.json`).
+//!
+//! Both layouts used by the desktop application are supported: the array of
+//! blocks with optional `match`, `match-type`, `options` and `bindings` keys,
+//! and the older object keyed by regular expressions:
+//!
+//! ```json
+//! {
+//!     ".*": { "grid": true, "render.background.color": "0.2,0.2,0.2" },
+//!     ".*(gltf|glb)": { "animation-time": 2 }
+//! }
+//! ```
+//!
+//! Blocks are applied in file order, so later matching blocks override earlier ones.
+
+use crate::error::{Error, Result};
+use crate::interactor::{InteractionBind, Interactor, InteractorBindType};
+use crate::options::{Options, OptionsMap};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Binding group used for bindings coming from configuration files.
+pub const CONFIG_BINDING_GROUP: &str = "Config";
+
+/// Application option names and the libf3d option they control.
+const APPLICATION_OPTIONS: &[(&str, &str)] = &[
+    ("ambient-occlusion", "render.effect.ambient_occlusion"),
+    ("animation-autoplay", "scene.animation.autoplay"),
+    ("animation-frame-rate", "scene.animation.frame_rate"),
+    ("animation-indices", "scene.animation.indices"),
+    ("animation-progress", "ui.animation_progress"),
+    ("animation-speed-factor", "scene.animation.speed_factor"),
+    ("animation-time", "scene.animation.time"),
+    ("anti-aliasing", "render.effect.antialiasing.enable"),
+    ("anti-aliasing-mode", "render.effect.antialiasing.mode"),
+    ("armature", "render.armature.enable"),
+    ("axis", "ui.axis"),
+    ("backface-type", "render.backface_type"),
+    ("background-color", "render.background.color"),
+    ("bg-color", "render.background.color"),
+    ("blur-background", "render.background.blur.enable"),
+    ("blur-coc", "render.background.blur.coc"),
+    ("camera-index", "scene.camera.index"),
+    ("camera-orthographic", "scene.camera.orthographic"),
+    ("cells", "model.scivis.cells"),
+    ("color", "model.color.rgb"),
+    ("coloring-array", "model.scivis.array_name"),
+    ("coloring-component", "model.scivis.component"),
+    ("coloring-range", "model.scivis.range"),
+    ("colormap", "model.scivis.colormap"),
+    ("comp", "model.scivis.component"),
+    ("denoise", "render.raytracing.denoise"),
+    ("edges", "render.show_edges"),
+    ("emissive-factor", "model.emissive.factor"),
+    ("filename", "ui.filename"),
+    ("final-shader", "render.effect.final_shader"),
+    ("font-file", "ui.font_file"),
+    ("force-reader", "scene.force_reader"),
+    ("fps", "ui.fps"),
+    ("grid", "render.grid.enable"),
+    ("grid-absolute", "render.grid.absolute"),
+    ("grid-color", "render.grid.color"),
+    ("grid-subdivisions", "render.grid.subdivisions"),
+    ("grid-unit", "render.grid.unit"),
+    ("hdri-ambient", "render.hdri.ambient"),
+    ("hdri-file", "render.hdri.file"),
+    ("hdri-skybox", "render.background.skybox"),
+    ("inverse", "model.volume.inverse"),
+    ("invert-zoom", "interactor.invert_zoom"),
+    ("light-intensity", "render.light.intensity"),
+    ("line-width", "render.line_width"),
+    ("metadata", "ui.metadata"),
+    ("metallic", "model.material.metallic"),
+    ("normal-scale", "model.normal.scale"),
+    ("opacity", "model.color.opacity"),
+    ("point-size", "render.point_size"),
+    ("point-sprites", "model.point_sprites.enable"),
+    ("point-sprites-size", "model.point_sprites.size"),
+    ("point-sprites-type", "model.point_sprites.type"),
+    ("progress", "ui.loader_progress"),
+    ("raytracing", "render.raytracing.enable"),
+    ("roughness", "model.material.roughness"),
+    ("samples", "render.raytracing.samples"),
+    ("scalar-bar", "ui.scalar_bar"),
+    ("scalar-coloring", "model.scivis.enable"),
+    ("texture-base-color", "model.color.texture"),
+    ("texture-emissive", "model.emissive.texture"),
+    ("texture-matcap", "model.matcap.texture"),
+    ("texture-material", "model.material.texture"),
+    ("texture-normal", "model.normal.texture"),
+    ("tone-mapping", "render.effect.tone_mapping"),
+    ("trackball", "interactor.trackball"),
+    ("translucency-support", "render.effect.translucency_support"),
+    ("up", "scene.up_direction"),
+    ("volume", "model.volume.enable"),
+];
+
+/// Maps an application option name, or a libf3d key, to a libf3d key.
+pub fn resolve_option_name(name: &str) -> Option<&str> {
+    if name.contains('.') {
+        return Some(name);
+    }
+    APPLICATION_OPTIONS
+        .binary_search_by_key(&name, |(app_name, _)| app_name)
+        .ok()
+        .map(|index| APPLICATION_OPTIONS[index].1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchType {
+    Regex,
+    Glob,
+    Exact,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigBlock {
+    pub pattern: Option<String>,
+    pub match_type: MatchType,
+    /// Option names as written in the file, mapped to libf3d string representations.
+    pub options: BTreeMap<String, String>,
+    /// Bind strings mapped to the commands they trigger.
+    pub bindings: BTreeMap<String, Vec<String>>,
+    matcher: Option<Regex>,
+}
+
+impl ConfigBlock {
+    fn new(pattern: Option<String>, match_type: MatchType) -> Result<Self> {
+        let matcher = match (&pattern, match_type) {
+            (Some(pattern), MatchType::Regex) => Some(full_match_regex(pattern)?),
+            (Some(pattern), MatchType::Glob) => Some(full_match_regex(&glob_to_regex(pattern))?),
+            _ => None,
+        };
+        Ok(Self {
+            pattern,
+            match_type,
+            options: BTreeMap::new(),
+            bindings: BTreeMap::new(),
+            matcher,
+        })
+    }
+
+    /// `block 2` or, for blocks with a pattern, `block 2 (.*gltf)`.
+    fn describe(&self, index: usize) -> String {
+        match &self.pattern {
+            Some(pattern) => format!("block {index} ({pattern})"),
+            None => format!("block {index}"),
+        }
+    }
+
+    /// Whether this block applies to `model_path`. Blocks without a pattern always apply.
+    pub fn matches(&self, model_path: &str) -> bool {
+        match (&self.pattern, &self.matcher) {
+            (None, _) => true,
+            (Some(_), Some(regex)) => regex.is_match(model_path),
+            (Some(pattern), None) => pattern == model_path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConfigFile {
+    pub blocks: Vec<ConfigBlock>,
+    /// File the blocks were loaded from, named in errors.
+    pub path: Option<PathBuf>,
+}
+
+impl ConfigFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let in_file =
+            |reason: String| Error::InvalidConfig(format!("{}: {reason}", path.display()));
+        let content = std::fs::read_to_string(path).map_err(|err| in_file(err.to_string()))?;
+        let mut config = Self::parse(&content).map_err(|err| match err {
+            Error::InvalidConfig(reason) => in_file(reason),
+            other => other,
+        })?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let root: Value =
+            serde_json::from_str(content).map_err(|err| Error::InvalidConfig(err.to_string()))?;
+
+        let blocks = match root {
+            Value::Array(entries) => entries
+                .iter()
+                .enumerate()
+                .map(|(index, entry)| {
+                    parse_block(entry).map_err(|err| match err {
+                        Error::InvalidConfig(reason) => {
+                            Error::InvalidConfig(format!("block {index}: {reason}"))
+                        }
+                        other => other,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Value::Object(entries) => entries
+                .iter()
+                .map(|(pattern, options)| {
+                    let mut block = ConfigBlock::new(Some(pattern.clone()), MatchType::Regex)?;
+                    block.options = parse_options(options)?;
+                    Ok(block)
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => {
+                return Err(Error::InvalidConfig(
+                    "expected an array or an object at the root".to_string(),
+                ));
+            }
+        };
+
+        Ok(Self { blocks, path: None })
+    }
+
+    pub fn matching_blocks<'c>(
+        &'c self,
+        model_path: &'c str,
+    ) -> impl Iterator<Item = &'c ConfigBlock> + 'c {
+        self.blocks
+            .iter()
+            .filter(move |block| block.matches(model_path))
+    }
+
+    /// Merged options of every block matching `model_path`, keyed by libf3d option name.
+    /// Names that are neither libf3d keys nor known application options are skipped.
+    pub fn options_for(&self, model_path: &str) -> OptionsMap {
+        let mut map = OptionsMap::new();
+        for block in self.matching_blocks(model_path) {
+            for (name, value) in &block.options {
+                if let Some(key) = resolve_option_name(name) {
+                    map.insert(key.to_string(), value.clone());
+                }
+            }
+        }
+        map
+    }
+
+    /// Merged bindings of every block matching `model_path`.
+    pub fn bindings_for(&self, model_path: &str) -> BTreeMap<String, Vec<String>> {
+        let mut bindings = BTreeMap::new();
+        for block in self.matching_blocks(model_path) {
+            bindings.extend(block.bindings.clone());
+        }
+        bindings
+    }
+
+    /// Applies the blocks matching `model_path` to `options` and `interactor`,
+    /// returning the option names that could not be mapped to libf3d options.
+    pub fn apply(
+        &self,
+        model_path: &str,
+        options: &Options,
+        interactor: &Interactor,
+    ) -> Result<Vec<String>> {
+        let mut ignored = Vec::new();
+        for block in self.matching_blocks(model_path) {
+            for name in block.options.keys() {
+                if resolve_option_name(name).is_none() && !ignored.contains(name) {
+                    ignored.push(name.clone());
+                }
+            }
+        }
+
+        // Set block by block, which gives the same result as `options_for` but lets
+        // errors name the entry that caused them.
+        for (index, block) in self.blocks.iter().enumerate() {
+            if !block.matches(model_path) {
+                continue;
+            }
+            for (name, value) in &block.options {
+                if let Some(key) = resolve_option_name(name) {
+                    options
+                        .set_string_representation(key, value)
+                        .map_err(|err| {
+                            self.entry_error(
+                                &format!("{}, option `{name}`", block.describe(index)),
+                                err,
+                            )
+                        })?;
+                }
+            }
+        }
+
+        for (bind, commands) in self.bindings_for(model_path) {
+            let bind = InteractionBind::parse(&bind)
+                .map_err(|err| self.entry_error(&format!("binding `{bind}`"), err))?;
+            interactor.remove_binding(bind.clone())?;
+            interactor.add_binding(
+                bind,
+                commands,
+                Some(CONFIG_BINDING_GROUP),
+                InteractorBindType::InteractorBindingOther,
+                0,
+            )?;
+        }
+
+        Ok(ignored)
+    }
+
+    fn entry_error(&self, entry: &str, err: Error) -> Error {
+        let source = match &self.path {
+            Some(path) => path.display().to_string(),
+            None => "configuration".to_string(),
+        };
+        Error::InvalidConfig(format!("{source}: {entry}: {err}"))
+    }
+}
+
+fn parse_block(entry: &Value) -> Result<ConfigBlock> {
+    let entry = entry
+        .as_object()
+        .ok_or_else(|| Error::InvalidConfig("config blocks must be objects".to_string()))?;
+
+    let pattern = match entry.get("match") {
+        None => None,
+        Some(Value::String(pattern)) => Some(pattern.clone()),
+        Some(_) => return Err(Error::InvalidConfig("`match` must be a string".to_string())),
+    };
+
+    let match_type = match entry.get("match-type").and_then(Value::as_str) {
+        None | Some("regex") => MatchType::Regex,
+        Some("glob") => MatchType::Glob,
+        Some("exact") => MatchType::Exact,
+        Some(other) => {
+            return Err(Error::InvalidConfig(format!(
+                "unknown match-type `{other}`"
+            )));
+        }
+    };
+
+    let mut block = ConfigBlock::new(pattern, match_type)?;
+    if let Some(options) = entry.get("options") {
+        block.options = parse_options(options)?;
+    }
+    if let Some(bindings) = entry.get("bindings") {
+        block.bindings = parse_bindings(bindings)?;
+    }
+    Ok(block)
+}
+
+fn parse_options(options: &Value) -> Result<BTreeMap<String, String>> {
+    let options = options
+        .as_object()
+        .ok_or_else(|| Error::InvalidConfig("`options` must be an object".to_string()))?;
+
+    let mut map = BTreeMap::new();
+    for (name, value) in options {
+        let repr = match value {
+            Value::Null => continue,
+            Value::Bool(value) => value.to_string(),
+            Value::Number(value) => value.to_string(),
+            Value::String(value) => value.clone(),
+            Value::Array(values) => values
+                .iter()
+                .map(|value| match value {
+                    Value::String(value) => value.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+            Value::Object(_) => {
+                return Err(Error::InvalidConfig(format!(
+                    "option `{name}` cannot be an object"
+                )));
+            }
+        };
+        map.insert(name.clone(), repr);
+    }
+    Ok(map)
+}
+
+fn parse_bindings(bindings: &Value) -> Result<BTreeMap<String, Vec<String>>> {
+    let bindings = bindings
+        .as_object()
+        .ok_or_else(|| Error::InvalidConfig("`bindings` must be an object".to_string()))?;
+
+    let mut map = BTreeMap::new();
+    for (bind, commands) in bindings {
+        let commands = match commands {
+            Value::String(command) => vec![command.clone()],
+            Value::Array(commands) => commands
+                .iter()
+                .map(|command| {
+                    command.as_str().map(str::to_string).ok_or_else(|| {
+                        Error::InvalidConfig(format!("commands of `{bind}` must be strings"))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => {
+                return Err(Error::InvalidConfig(format!(
+                    "binding `{bind}` must be a string or an array of strings"
+                )));
+            }
+        };
+        map.insert(bind.clone(), commands);
+    }
+    Ok(map)
+}
+
+fn full_match_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(&format!("^(?:{pattern})$"))
+        .map_err(|err| Error::InvalidConfig(format!("invalid pattern `{pattern}`: {err}")))
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    glob.chars()
+        .map(|ch| match ch {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            other => regex::escape(&other.to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn application_options_are_sorted() {
+        assert!(
+            APPLICATION_OPTIONS
+                .windows(2)
+                .all(|pair| pair[0].0 < pair[1].0)
+        );
+    }
+
+    #[test]
+    fn resolves_option_names() {
+        assert_eq!(resolve_option_name("grid"), Some("render.grid.enable"));
+        assert_eq!(
+            resolve_option_name("bg-color"),
+            Some("render.background.color")
+        );
+        assert_eq!(
+            resolve_option_name("render.grid.unit"),
+            Some("render.grid.unit")
+        );
+        assert_eq!(resolve_option_name("no-such-option"), None);
+    }
+
+    #[test]
+    fn parses_both_layouts() {
+        let config = ConfigFile::parse(
+            r#"[
+                { "options": { "grid": true, "up": [0, 0, 1], "hdri-file": null } },
+                { "match": ".*gltf", "options": { "animation-time": 2.5, "font-file": "a b.ttf" } }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(config.blocks.len(), 2);
+        assert_eq!(config.blocks[0].pattern, None);
+        let options = &config.blocks[0].options;
+        assert_eq!(options["grid"], "true");
+        assert_eq!(options["up"], "0,0,1");
+        assert!(!options.contains_key("hdri-file"));
+        assert_eq!(config.blocks[1].options["animation-time"], "2.5");
+        assert_eq!(config.blocks[1].options["font-file"], "a b.ttf");
+
+        let config =
+            ConfigFile::parse(r#"{ ".*": { "grid": false }, ".*stl": { "edges": true } }"#)
+                .unwrap();
+        assert_eq!(config.blocks.len(), 2);
+        assert_eq!(config.blocks[1].match_type, MatchType::Regex);
+        assert_eq!(
+            config.options_for("model.stl"),
+            OptionsMap::from([
+                ("render.grid.enable".to_string(), "false".to_string()),
+                ("render.show_edges".to_string(), "true".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn matches_regex_glob_and_exact_patterns() {
+        let block = |pattern: &str, match_type| {
+            ConfigBlock::new(Some(pattern.to_string()), match_type).unwrap()
+        };
+        let regex = block(r".*\.(gltf|glb)", MatchType::Regex);
+        assert!(regex.matches("dir/model.glb"));
+        assert!(!regex.matches("model.glb.bak"));
+
+        let glob = block("*.st?", MatchType::Glob);
+        assert!(glob.matches("dir/cube.stl"));
+        assert!(!glob.matches("cube.obj"));
+        assert!(block("a+b.obj", MatchType::Glob).matches("a+b.obj"));
+
+        let exact = block("cube.stl", MatchType::Exact);
+        assert!(exact.matches("cube.stl"));
+        assert!(!exact.matches("dir/cube.stl"));
+
+        assert!(
+            ConfigBlock::new(None, MatchType::Exact)
+                .unwrap()
+                .matches("anything")
+        );
+        assert!(ConfigBlock::new(Some("(".to_string()), MatchType::Regex).is_err());
+    }
+
+    #[test]
+    fn parses_and_merges_bindings() {
+        let config = ConfigFile::parse(
+            r#"[
+                { "bindings": { "Ctrl+O": "open_file_dialog", "G": ["toggle render.grid.enable", "render"] } },
+                { "match": ".*obj", "bindings": { "G": "reset_camera" } }
+            ]"#,
+        )
+        .unwrap();
+        let bindings = config.bindings_for("cube.stl");
+        assert_eq!(bindings["Ctrl+O"], ["open_file_dialog"]);
+        assert_eq!(bindings["G"], ["toggle render.grid.enable", "render"]);
+        assert_eq!(config.bindings_for("cube.obj")["G"], ["reset_camera"]);
+
+        let err = ConfigFile::parse(r#"[{ "bindings": { "G": [1] } }]"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: block 0: commands of `G` must be strings"
+        );
+    }
+
+    #[test]
+    fn reports_invalid_files() {
+        for (content, reason) in [
+            ("1", "expected an array or an object at the root"),
+            (
+                r#"[{}, { "match-type": "fuzzy" }]"#,
+                "block 1: unknown match-type `fuzzy`",
+            ),
+            (
+                r#"[{ "options": { "grid": {} } }]"#,
+                "block 0: option `grid` cannot be an object",
+            ),
+        ] {
+            let err = ConfigFile::parse(content).unwrap_err();
+            assert_eq!(err.to_string(), format!("invalid configuration: {reason}"));
+        }
+
+        let path = std::env::temp_dir().join(format!("f3d-config-{}.json", std::process::id()));
+        std::fs::write(&path, "[1]").unwrap();
+        let err = ConfigFile::load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid configuration: {}: block 0: config blocks must be objects",
+                path.display()
+            )
+        );
+    }
+}
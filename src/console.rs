@@ -0,0 +1,430 @@
+//! Command scripts and an interactive console for the interactor.
+//!
+//! Scripts hold one libf3d command per line. On top of those, the console
+//! understands two directives:
+//!
+//! - `let name = value` defines a variable, expanded as `${name}` in later lines;
+//! - `include path` runs another script, relative to the including one.
+//!
+//! ```text
+//! # turntable setup
+//! let color = 0.2,0.2,0.3
+//! set render.background.color ${color}
+//! include lights.f3d
+//! ```
+
+use crate::error::{Error, Result};
+use crate::interactor::Interactor;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Something that runs libf3d command lines, usually an [`Interactor`].
+pub trait CommandTarget {
+    fn run_command(&mut self, command: &str, keep_comments: bool) -> Result<()>;
+}
+
+impl CommandTarget for Interactor<'_> {
+    fn run_command(&mut self, command: &str, keep_comments: bool) -> Result<()> {
+        self.trigger_command(command.to_string(), keep_comments)
+    }
+}
+
+impl<F: FnMut(&str, bool) -> Result<()>> CommandTarget for F {
+    fn run_command(&mut self, command: &str, keep_comments: bool) -> Result<()> {
+        self(command, keep_comments)
+    }
+}
+
+/// A script line that failed, with the script it comes from.
+#[derive(Debug)]
+pub struct LineError {
+    /// Script path, or `<input>` for scripts given as text.
+    pub script: String,
+    pub line: usize,
+    pub command: String,
+    pub error: Error,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: `{}`: {}",
+            self.script, self.line, self.command, self.error
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScriptReport {
+    /// Commands sent to the target, included scripts counted in.
+    pub executed: usize,
+    pub errors: Vec<LineError>,
+}
+
+impl ScriptReport {
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+const INPUT: &str = "<input>";
+
+pub struct Console<T: CommandTarget> {
+    target: T,
+    variables: BTreeMap<String, String>,
+    keep_comments: bool,
+    stop_on_error: bool,
+    /// Scripts being run, to reject recursive includes.
+    includes: Vec<PathBuf>,
+}
+
+impl<T: CommandTarget> Console<T> {
+    pub fn new(target: T) -> Self {
+        Self {
+            target,
+            variables: BTreeMap::new(),
+            keep_comments: false,
+            stop_on_error: false,
+            includes: Vec::new(),
+        }
+    }
+
+    /// Passed to [`Interactor::trigger_command`]; when unset, `#` starts a comment.
+    pub fn keep_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+
+    /// Stops a script at its first failing line instead of reporting every failure.
+    pub fn stop_on_error(mut self, stop_on_error: bool) -> Self {
+        self.stop_on_error = stop_on_error;
+        self
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: impl Into<String>) -> &mut Self {
+        self.variables.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(String::as_str)
+    }
+
+    pub fn variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+
+    pub fn target(&mut self) -> &mut T {
+        &mut self.target
+    }
+
+    pub fn into_target(self) -> T {
+        self.target
+    }
+
+    /// Runs a single line; includes resolve against the working directory.
+    pub fn run_line(&mut self, line: &str) -> Result<ScriptReport> {
+        let mut report = ScriptReport::default();
+        self.run_line_in(line, None, &mut report).map(|()| report)
+    }
+
+    pub fn run_script(&mut self, source: &str) -> ScriptReport {
+        let mut report = ScriptReport::default();
+        self.run_source(source, INPUT, None, &mut report);
+        report
+    }
+
+    pub fn run_file(&mut self, path: impl AsRef<Path>) -> Result<ScriptReport> {
+        let mut report = ScriptReport::default();
+        self.include(path.as_ref(), &mut report)?;
+        Ok(report)
+    }
+
+    /// Reads commands from `input` until it ends or `exit` is typed, printing failures to `output`.
+    pub fn repl(&mut self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if matches!(line.trim(), "exit" | "quit") {
+                break;
+            }
+            match self.run_line(&line) {
+                Ok(report) => {
+                    for error in report.errors {
+                        writeln!(output, "{error}")?;
+                    }
+                }
+                Err(err) => writeln!(output, "error: {err}")?,
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// [`repl`](Self::repl) over stdin and stdout.
+    pub fn repl_stdin(&mut self) -> Result<()> {
+        self.repl(std::io::stdin().lock(), std::io::stdout())
+    }
+
+    fn run_source(
+        &mut self,
+        source: &str,
+        script: &str,
+        dir: Option<&Path>,
+        report: &mut ScriptReport,
+    ) {
+        for (index, line) in source.lines().enumerate() {
+            if let Err(error) = self.run_line_in(line, dir, report) {
+                report.errors.push(LineError {
+                    script: script.to_string(),
+                    line: index + 1,
+                    command: line.trim().to_string(),
+                    error,
+                });
+            }
+            if self.stop_on_error && !report.errors.is_empty() {
+                return;
+            }
+        }
+    }
+
+    fn run_line_in(
+        &mut self,
+        line: &str,
+        dir: Option<&Path>,
+        report: &mut ScriptReport,
+    ) -> Result<()> {
+        let line = if self.keep_comments {
+            line.trim()
+        } else {
+            strip_comment(line).trim()
+        };
+        if line.is_empty() {
+            return Ok(());
+        }
+        let line = self.expand(line)?;
+
+        if let Some(definition) = directive(&line, "let") {
+            let (name, value) = definition
+                .split_once('=')
+                .ok_or_else(|| invalid("let", "expected `let name = value`"))?;
+            let name = name.trim();
+            if !is_variable_name(name) {
+                return Err(invalid("let", &format!("invalid variable name `{name}`")));
+            }
+            self.variables
+                .insert(name.to_string(), unquote(value.trim()).to_string());
+            return Ok(());
+        }
+        if let Some(path) = directive(&line, "include") {
+            let path = Path::new(unquote(path));
+            let path = match dir {
+                Some(dir) if path.is_relative() => dir.join(path),
+                _ => path.to_path_buf(),
+            };
+            return self.include(&path, report);
+        }
+
+        self.target.run_command(&line, self.keep_comments)?;
+        report.executed += 1;
+        Ok(())
+    }
+
+    fn include(&mut self, path: &Path, report: &mut ScriptReport) -> Result<()> {
+        let canonical = path.canonicalize()?;
+        if self.includes.contains(&canonical) {
+            return Err(invalid(
+                "include",
+                &format!("{} includes itself", path.display()),
+            ));
+        }
+        let source = std::fs::read_to_string(path)?;
+        self.includes.push(canonical);
+        self.run_source(&source, &path.display().to_string(), path.parent(), report);
+        self.includes.pop();
+        Ok(())
+    }
+
+    /// Replaces `${name}` with the variable value; `$${` escapes a literal `${`.
+    fn expand(&self, line: &str) -> Result<String> {
+        let mut expanded = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find('$') {
+            expanded.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(escaped) = rest.strip_prefix("$${") {
+                expanded.push_str("${");
+                rest = escaped;
+            } else if let Some(reference) = rest.strip_prefix("${") {
+                let end = reference
+                    .find('}')
+                    .ok_or_else(|| invalid("${", "unterminated variable reference"))?;
+                let name = &reference[..end];
+                let value = self
+                    .variables
+                    .get(name)
+                    .ok_or_else(|| invalid(name, "undefined variable"))?;
+                expanded.push_str(value);
+                rest = &reference[end + 1..];
+            } else {
+                expanded.push('$');
+                rest = &rest[1..];
+            }
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+}
+
+fn invalid(command: &str, reason: &str) -> Error {
+    Error::InvalidCommand {
+        command: command.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Arguments of `line` when it starts with the `name` directive.
+fn directive<'l>(line: &'l str, name: &str) -> Option<&'l str> {
+    let rest = line.strip_prefix(name)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|&quote| {
+            value
+                .strip_prefix(quote)
+                .and_then(|value| value.strip_suffix(quote))
+        })
+        .unwrap_or(value)
+}
+
+/// Cuts the line at the first `#` outside of quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (c, quote) {
+            _ if escaped => escaped = false,
+            ('\\', _) => escaped = true,
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            ('#', None) => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Recorded = std::rc::Rc<std::cell::RefCell<Vec<String>>>;
+
+    /// A console recording its commands and failing those starting with `fail`.
+    fn recording() -> (Console<impl FnMut(&str, bool) -> Result<()>>, Recorded) {
+        let commands = Recorded::default();
+        let sink = commands.clone();
+        let console = Console::new(move |command: &str, _keep_comments: bool| {
+            if command.starts_with("fail") {
+                return Err(Error::CommandFailed {
+                    command: command.to_string(),
+                    reason: "refused".to_string(),
+                });
+            }
+            sink.borrow_mut().push(command.to_string());
+            Ok(())
+        });
+        (console, commands)
+    }
+
+    #[test]
+    fn expands_variables_and_skips_comments() {
+        let (mut console, commands) = recording();
+        let report = console.run_script(
+            "# setup\n\
+             let color = \"0.1,0.2,0.3\"\n\
+             \n\
+             set render.background.color ${color} # dark\n\
+             print \"a # b\"\n\
+             print $${color} costs $5\n",
+        );
+        assert!(report.is_success(), "{:?}", report.errors);
+        assert_eq!(report.executed, 3);
+        assert_eq!(console.variable("color"), Some("0.1,0.2,0.3"));
+        assert_eq!(
+            *commands.borrow(),
+            [
+                "set render.background.color 0.1,0.2,0.3",
+                "print \"a # b\"",
+                "print ${color} costs $5",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_line_errors() {
+        let (mut console, _) = recording();
+        let report = console.run_script("toggle ui.fps\nfail now\nprint ${missing}\nlet 1x\n");
+        assert_eq!(report.executed, 1);
+        let lines: Vec<_> = report.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, [2, 3, 4]);
+        assert_eq!(
+            report.errors[1].to_string(),
+            "<input>:3: `print ${missing}`: invalid command missing: undefined variable"
+        );
+
+        let mut console = console.stop_on_error(true);
+        assert_eq!(console.run_script("fail\nprint x\n").executed, 0);
+    }
+
+    #[test]
+    fn includes_scripts_relative_to_the_includer() {
+        let dir = std::env::temp_dir().join(format!("f3d-console-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lights")).unwrap();
+        std::fs::write(
+            dir.join("main.f3d"),
+            "let name = key\ninclude lights/key.f3d\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("lights/key.f3d"),
+            "print ${name}\ninclude ../main.f3d\n",
+        )
+        .unwrap();
+
+        let (mut console, commands) = recording();
+        let report = console.run_file(dir.join("main.f3d")).unwrap();
+        assert_eq!(*commands.borrow(), ["print key"]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 2);
+        assert!(report.errors[0].script.ends_with("key.f3d"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn runs_a_repl() {
+        let (mut console, commands) = recording();
+        let mut output = Vec::new();
+        console
+            .repl(
+                "let x = 1\nprint ${x}\nprint ${y}\nexit\nprint late\n".as_bytes(),
+                &mut output,
+            )
+            .unwrap();
+        assert_eq!(*commands.borrow(), ["print 1"]);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("error: invalid command y: undefined variable"));
+    }
+}
@@ -1,30 +1,162 @@
-use crate::interactor::Interactor;
+use crate::error::{Error, Result, check};
+use crate::interactor::{CommandRegistry, Interactor};
 use crate::options::Options;
 use crate::scene::Scene;
 use crate::sys::*;
 use crate::window::Window;
-use std::ffi::{CStr, NulError};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::fmt;
+use std::panic::AssertUnwindSafe;
 use std::ptr::NonNull;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+
+/// Semantic version of libf3d, e.g. `3.1.0` or `3.2.0-RC1`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct F3DVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    /// Pre-release suffix after the `-`, which sorts before the release.
+    /// Numbers within it compare numerically, so `RC2` sorts before `RC10`.
+    pub pre: Option<String>,
+}
 
-#[derive(Debug, Clone)]
-pub struct F3DModuleInfo {
-    pub name: String,
-    pub available: bool,
+impl F3DVersion {
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+}
+
+impl FromStr for F3DVersion {
+    type Err = Error;
+
+    /// Accepts an optional `v` prefix and a missing patch number.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || Error::InvalidVersion(value.to_string());
+        let trimmed = value.trim().trim_start_matches('v');
+        let (numbers, pre) = match trimmed.split_once('-') {
+            Some((numbers, pre)) if !pre.is_empty() => (numbers, Some(pre.to_string())),
+            Some(_) => return Err(invalid()),
+            None => (trimmed, None),
+        };
+        let numbers = numbers
+            .split('.')
+            .map(|number| number.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+        let (major, minor, patch) = match numbers[..] {
+            [major, minor] => (major, minor, 0),
+            [major, minor, patch] => (major, minor, patch),
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+impl fmt::Display for F3DVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{pre}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for F3DVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(ours), Some(theirs)) => compare_pre_releases(ours, theirs),
+            })
+    }
+}
+
+/// Compares pre-release suffixes by runs of digits and non-digits, numbers by value.
+fn compare_pre_releases(ours: &str, theirs: &str) -> Ordering {
+    let (our_runs, their_runs) = (pre_release_runs(ours), pre_release_runs(theirs));
+    for (lhs, rhs) in our_runs.iter().zip(&their_runs) {
+        let ordering = match (lhs.parse::<u64>(), rhs.parse::<u64>()) {
+            (Ok(lhs), Ok(rhs)) => lhs.cmp(&rhs),
+            _ => lhs.cmp(rhs),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    // Falls back to the text so that only equal suffixes compare equal, e.g. `RC01` and `RC1`.
+    our_runs
+        .len()
+        .cmp(&their_runs.len())
+        .then_with(|| ours.cmp(theirs))
+}
+
+/// Splits `RC10.beta2` into `RC`, `10`, `beta` and `2`.
+fn pre_release_runs(pre: &str) -> Vec<&str> {
+    let mut runs = Vec::new();
+    for part in pre.split('.') {
+        let bytes = part.as_bytes();
+        let mut start = 0;
+        for end in 1..=bytes.len() {
+            if end == bytes.len() || bytes[end].is_ascii_digit() != bytes[end - 1].is_ascii_digit()
+            {
+                runs.push(&part[start..end]);
+                start = end;
+            }
+        }
+    }
+    runs
+}
+
+impl PartialOrd for F3DVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct F3DLibInfo {
     pub version: String,
+    /// `version` parsed, or `None` when libf3d reports an unexpected format.
+    pub parsed_version: Option<F3DVersion>,
     pub version_full: String,
     pub build_date: String,
     pub build_system: String,
     pub compiler: String,
-    pub modules: F3DModuleInfo,
+    /// Optional modules libf3d was built with, mapped to their availability.
+    pub modules: BTreeMap<String, bool>,
     pub vtk_version: String,
     pub copyrights: Vec<String>,
     pub license: String,
 }
 
+impl F3DLibInfo {
+    /// Case-insensitive module lookup, e.g. `has_module("raytracing")`.
+    pub fn has_module(&self, name: &str) -> bool {
+        self.modules
+            .iter()
+            .any(|(module, &available)| available && module.eq_ignore_ascii_case(name))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct F3DReaderInfo {
     pub name: String,
@@ -32,10 +164,29 @@ pub struct F3DReaderInfo {
     pub extensions: Vec<String>,
     pub mime_types: Vec<String>,
     pub plugin_name: String,
-    pub has_scene_reader: i32,
-    pub has_geometry_reader: i32,
+    pub has_scene_reader: bool,
+    pub has_geometry_reader: bool,
+}
+
+impl F3DReaderInfo {
+    /// Case-insensitive, with or without the leading dot.
+    pub fn supports_extension(&self, extension: &str) -> bool {
+        let extension = extension.trim_start_matches('.');
+        self.extensions.iter().any(|candidate| {
+            candidate
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+    }
+
+    pub fn supports_mime_type(&self, mime_type: &str) -> bool {
+        self.mime_types
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(mime_type))
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EngineBackend {
     Auto { offscreen: bool },
     None,
@@ -46,20 +197,163 @@ pub enum EngineBackend {
     Cocoa,
 }
 
+impl EngineBackend {
+    /// Key of the backend in [`Engine::get_rendering_backends`], if it is listed there.
+    pub fn backend_name(&self) -> Option<&'static str> {
+        match self {
+            EngineBackend::Glx { .. } => Some("glx"),
+            EngineBackend::Wgl { .. } => Some("wgl"),
+            EngineBackend::Egl => Some("egl"),
+            EngineBackend::Omesa => Some("osmesa"),
+            EngineBackend::Cocoa => Some("cocoa"),
+            EngineBackend::Auto { .. } | EngineBackend::None => None,
+        }
+    }
+
+    /// Order tried by [`Engine::best_available`].
+    pub fn default_priority(offscreen: bool) -> Vec<EngineBackend> {
+        if offscreen {
+            vec![
+                EngineBackend::Egl,
+                EngineBackend::Omesa,
+                EngineBackend::Glx { offscreen: true },
+                EngineBackend::Wgl { offscreen: true },
+                EngineBackend::None,
+            ]
+        } else {
+            vec![
+                EngineBackend::Glx { offscreen: false },
+                EngineBackend::Wgl { offscreen: false },
+                EngineBackend::Auto { offscreen: false },
+                EngineBackend::None,
+            ]
+        }
+    }
+}
+
+impl fmt::Display for EngineBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offscreen = |offscreen: bool| if offscreen { " (offscreen)" } else { "" };
+        match self {
+            EngineBackend::Auto { offscreen: o } => write!(f, "auto{}", offscreen(*o)),
+            EngineBackend::None => write!(f, "none"),
+            EngineBackend::Glx { offscreen: o } => write!(f, "GLX{}", offscreen(*o)),
+            EngineBackend::Wgl { offscreen: o } => write!(f, "WGL{}", offscreen(*o)),
+            EngineBackend::Egl => write!(f, "EGL"),
+            EngineBackend::Omesa => write!(f, "OSMesa"),
+            EngineBackend::Cocoa => write!(f, "Cocoa"),
+        }
+    }
+}
+
+/// Resolves OpenGL function pointers by name, e.g. `glutin`'s `get_proc_address`.
+pub type ProcAddressLoader = dyn Fn(&str) -> *const c_void;
+
 pub enum EngineContext {
     Internal,
     ExternalPlatform,
-    //TODO: External get_proc_address
+    /// OpenGL context owned by the application, which must be current whenever
+    /// the engine renders. Only valid with [`EngineBackend::Auto`].
+    ///
+    /// libf3d resolves functions through a single callback, so a thread can only run
+    /// one such engine at a time: creating another fails until the first is dropped.
+    External {
+        loader: Box<ProcAddressLoader>,
+    },
+}
+
+impl fmt::Debug for EngineContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineContext::Internal => write!(f, "Internal"),
+            EngineContext::ExternalPlatform => write!(f, "ExternalPlatform"),
+            EngineContext::External { .. } => write!(f, "External"),
+        }
+    }
+}
+
+thread_local! {
+    /// Loader used by [`get_proc_address`]; libf3d gives the callback no user data and
+    /// OpenGL contexts are bound to the thread rendering with them.
+    static EXTERNAL_LOADER: RefCell<Option<Rc<ProcAddressLoader>>> = const { RefCell::new(None) };
+}
+
+unsafe extern "C" fn get_proc_address(name: *const c_char) -> f3d_context_function_t {
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    // Unwinding into libf3d would abort, so a panicking loader resolves nothing.
+    let address = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        EXTERNAL_LOADER.with(|loader| loader.borrow().as_ref().map(|loader| loader(&name)))
+    }))
+    .ok()
+    .flatten()
+    .unwrap_or(std::ptr::null());
+    if address.is_null() {
+        return None;
+    }
+    Some(unsafe { std::mem::transmute::<*const c_void, unsafe extern "C" fn()>(address) })
+}
+
+/// `f3d_log_verbose_level_t` of error messages.
+const LOG_ERROR: f3d_log_verbose_level_t = 3;
+
+thread_local! {
+    /// Errors logged by libf3d while [`Engine::load_plugin`] runs, its only way to report why.
+    static PLUGIN_ERRORS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Forwarder installed with [`Engine::set_log_forward`], since libf3d cannot report it.
+static LOG_FORWARD: Mutex<f3d_log_forward_callback_t> = Mutex::new(None);
+
+fn log_forward() -> f3d_log_forward_callback_t {
+    *LOG_FORWARD.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+unsafe extern "C" fn collect_plugin_error(level: f3d_log_verbose_level_t, message: *const c_char) {
+    if level >= LOG_ERROR && !message.is_null() {
+        let text = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+        PLUGIN_ERRORS.with(|errors| errors.borrow_mut().push(text.trim().to_string()));
+    }
+    if let Some(forward) = log_forward() {
+        unsafe { forward(level, message) };
+    }
 }
 
 pub struct Engine {
     ptr: NonNull<f3d_engine_t>,
+    /// Keeps the external context loader alive while libf3d may call it.
+    loader: Option<Rc<ProcAddressLoader>>,
+    commands: CommandRegistry,
+}
+
+/// A backend that failed before [`Engine::best_available`] found a working one.
+#[derive(Debug)]
+pub struct BackendAttempt {
+    pub backend: EngineBackend,
+    pub error: Error,
+}
+
+pub struct BackendSelection {
+    pub engine: Engine,
+    pub backend: EngineBackend,
+    /// Backends tried first, in priority order.
+    pub attempts: Vec<BackendAttempt>,
 }
 
 impl Engine {
-    pub fn new(backend: EngineBackend, context: EngineContext) -> Self {
+    pub fn new(backend: EngineBackend, context: EngineContext) -> Result<Self> {
+        let failed = |reason: &str| Error::EngineCreationFailed {
+            backend: backend.to_string(),
+            reason: reason.to_string(),
+        };
+        if let EngineContext::External { loader } = context {
+            if !matches!(backend, EngineBackend::Auto { .. }) {
+                return Err(failed("an external context requires the auto backend"));
+            }
+            return Self::create_external(Rc::from(loader)).map_err(failed);
+        }
+
         let ptr = unsafe {
-            match (backend, context) {
+            match (backend, &context) {
                 (EngineBackend::Auto { offscreen }, EngineContext::Internal) => {
                     f3d_engine_create(offscreen as i32)
                 }
@@ -90,12 +384,101 @@ impl Engine {
                     f3d_engine_create_external_cocoa()
                 }
 
-                _ => panic!("invalid backend/context combination"),
+                _ => {
+                    return Err(failed(&format!(
+                        "invalid backend/context combination with {context:?} context"
+                    )));
+                }
             }
         };
-        Self {
-            ptr: NonNull::new(ptr).expect("f3d_engine_create returned null"),
+        NonNull::new(ptr)
+            .map(|ptr| Self {
+                ptr,
+                loader: None,
+                commands: CommandRegistry::default(),
+            })
+            .ok_or_else(|| failed("libf3d could not create the engine"))
+    }
+
+    fn create_external(loader: Rc<ProcAddressLoader>) -> std::result::Result<Self, &'static str> {
+        let claimed = EXTERNAL_LOADER.with(|current| {
+            let mut current = current.borrow_mut();
+            if current.is_some() {
+                return false;
+            }
+            *current = Some(loader.clone());
+            true
+        });
+        if !claimed {
+            return Err("another engine with an external context is alive on this thread");
+        }
+        let ptr = unsafe { f3d_engine_create_external(Some(get_proc_address)) };
+        match NonNull::new(ptr) {
+            Some(ptr) => Ok(Self {
+                ptr,
+                loader: Some(loader),
+                commands: CommandRegistry::default(),
+            }),
+            None => {
+                EXTERNAL_LOADER.with(|current| current.replace(None));
+                Err("libf3d could not create the engine")
+            }
+        }
+    }
+
+    /// Creates an engine with the first working backend of [`EngineBackend::default_priority`].
+    pub fn best_available(offscreen: bool) -> Result<BackendSelection> {
+        Self::best_available_in(&EngineBackend::default_priority(offscreen))
+    }
+
+    /// Tries `priority` in order, skipping backends libf3d reports as unavailable.
+    pub fn best_available_in(priority: &[EngineBackend]) -> Result<BackendSelection> {
+        let availability = Self::get_rendering_backends();
+        let mut attempts = Vec::new();
+
+        for &backend in priority {
+            let available = backend.backend_name().and_then(|name| {
+                availability
+                    .iter()
+                    .find(|(backend, _)| backend == name)
+                    .map(|&(_, available)| available)
+            });
+            let outcome = if available == Some(false) {
+                Err(Error::EngineCreationFailed {
+                    backend: backend.to_string(),
+                    reason: "not available in this libf3d build".to_string(),
+                })
+            } else {
+                Self::new(backend, EngineContext::Internal)
+            };
+
+            match outcome {
+                Ok(engine) => {
+                    return Ok(BackendSelection {
+                        engine,
+                        backend,
+                        attempts,
+                    });
+                }
+                Err(err) => attempts.push(BackendAttempt {
+                    backend,
+                    error: err,
+                }),
+            }
         }
+
+        let reasons: Vec<String> = attempts
+            .iter()
+            .map(|attempt| attempt.error.to_string())
+            .collect();
+        Err(Error::EngineCreationFailed {
+            backend: "every backend".to_string(),
+            reason: if reasons.is_empty() {
+                "no backend to try".to_string()
+            } else {
+                reasons.join("; ")
+            },
+        })
     }
 
     pub fn auto_load_plugins() {
@@ -104,26 +487,76 @@ impl Engine {
         }
     }
 
-    pub fn set_cache_path(&self, path: &str) {
-        let c_path = std::ffi::CString::new(path).expect("CString::new failed");
+    pub fn set_cache_path(&self, path: &str) -> Result<()> {
+        let c_path = CString::new(path)?;
         unsafe {
             f3d_engine_set_cache_path(self.ptr.as_ptr(), c_path.as_ptr());
         }
+        Ok(())
     }
 
-    pub fn options(&self) -> Options {
+    /// The returned handle borrows the engine, so it cannot outlive it:
+    ///
+    /// ```compile_fail,E0597
+    /// use f3d::engine::{Engine, EngineBackend, EngineContext};
+    ///
+    /// let options = {
+    ///     let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+    ///     engine.options()
+    /// };
+    /// options.set_bool("render.grid.enable", true).unwrap();
+    /// ```
+    pub fn options(&self) -> Options<'_> {
         unsafe { Options::from_raw(f3d_engine_get_options(self.ptr.as_ptr())) }
     }
 
-    pub fn scene(&self) -> Scene {
-        unsafe { Scene::from_raw(f3d_engine_get_scene(self.ptr.as_ptr())) }
+    /// The returned handle borrows the engine, so the engine cannot be dropped while it is alive:
+    ///
+    /// ```compile_fail,E0505
+    /// use f3d::engine::{Engine, EngineBackend, EngineContext};
+    ///
+    /// let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+    /// let scene = engine.scene();
+    /// drop(engine);
+    /// scene.clear();
+    /// ```
+    pub fn scene(&self) -> Scene<'_> {
+        unsafe {
+            Scene::from_raw(
+                f3d_engine_get_scene(self.ptr.as_ptr()),
+                f3d_engine_get_options(self.ptr.as_ptr()),
+            )
+        }
     }
 
-    pub fn interactor(&self) -> Interactor {
-        unsafe { Interactor::from_raw(f3d_engine_get_interactor(self.ptr.as_ptr())) }
+    /// The returned handle borrows the engine, so it cannot be moved out of its scope:
+    ///
+    /// ```compile_fail,E0515
+    /// use f3d::engine::{Engine, EngineBackend, EngineContext};
+    /// use f3d::interactor::Interactor;
+    ///
+    /// fn make_interactor() -> Interactor<'static> {
+    ///     let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+    ///     engine.interactor()
+    /// }
+    /// ```
+    pub fn interactor(&self) -> Interactor<'_> {
+        unsafe {
+            Interactor::from_raw(f3d_engine_get_interactor(self.ptr.as_ptr()), &self.commands)
+        }
     }
 
-    pub fn window(&self) -> Window {
+    /// The returned handle borrows the engine, and so does every camera obtained from it:
+    ///
+    /// ```compile_fail,E0505
+    /// use f3d::engine::{Engine, EngineBackend, EngineContext};
+    ///
+    /// let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+    /// let camera = engine.window().camera();
+    /// drop(engine);
+    /// camera.camera_dolly(1.5);
+    /// ```
+    pub fn window(&self) -> Window<'_> {
         unsafe {
             let ptr = f3d_engine_get_window(self.ptr.as_ptr());
             Window::from_raw(ptr)
@@ -156,17 +589,43 @@ impl Engine {
         }
     }
 
-    pub fn load_plugin(plugin_path: &str) ->Result<i32, NulError> {
-        let c_plugin_path = std::ffi::CString::new(plugin_path).expect("CString::new failed");
-        unsafe { Ok(f3d_engine_load_plugin(c_plugin_path.as_ptr())) }
+    /// Failures carry the reason libf3d logged, e.g. a missing library or symbol.
+    pub fn load_plugin(plugin_path: &str) -> Result<()> {
+        let c_plugin_path = CString::new(plugin_path)?;
+        PLUGIN_ERRORS.with(|errors| errors.borrow_mut().clear());
+        // Messages still reach the application forwarder, which is reinstalled afterwards.
+        let status = unsafe {
+            f3d_log_forward(Some(collect_plugin_error));
+            let status = f3d_engine_load_plugin(c_plugin_path.as_ptr());
+            f3d_log_forward(log_forward());
+            status
+        };
+        let errors = PLUGIN_ERRORS.with(|errors| errors.take());
+        check(status, || {
+            Error::PluginLoadFailed(if errors.is_empty() {
+                plugin_path.to_string()
+            } else {
+                format!("{plugin_path}: {}", errors.join("; "))
+            })
+        })
     }
 
-    pub fn get_plugins_list(path: &str) -> Vec<String> {
-        let c_path = std::ffi::CString::new(path).expect("CString::new failed");
+    /// Forwards libf3d log messages to `callback`, or stops forwarding them with `None`.
+    ///
+    /// Use this rather than `f3d_log_forward`: libf3d cannot report the current forwarder,
+    /// so [`load_plugin`](Self::load_plugin) only restores one installed here.
+    pub fn set_log_forward(callback: f3d_log_forward_callback_t) {
+        let mut forward = LOG_FORWARD.lock().unwrap_or_else(PoisonError::into_inner);
+        *forward = callback;
+        unsafe { f3d_log_forward(callback) };
+    }
+
+    pub fn get_plugins_list(path: &str) -> Result<Vec<String>> {
+        let c_path = CString::new(path)?;
         unsafe {
             let ptr = f3d_engine_get_plugins_list(c_path.as_ptr());
             if ptr.is_null() {
-                return Vec::new();
+                return Ok(Vec::new());
             }
 
             let mut plugins = Vec::new();
@@ -183,7 +642,7 @@ impl Engine {
 
             f3d_engine_free_string_array(ptr);
 
-            plugins
+            Ok(plugins)
         }
     }
 
@@ -212,19 +671,29 @@ impl Engine {
         }
     }
 
-    pub fn set_reader_option(name: &str, value: &str) {
-        let c_name = std::ffi::CString::new(name).expect("CString::new failed");
-        let c_value = std::ffi::CString::new(value).expect("CString::new failed");
+    /// Fails with [`Error::InvalidOptionKey`] for options no registered reader provides,
+    /// which libf3d would only log.
+    pub fn set_reader_option(name: &str, value: &str) -> Result<()> {
+        let c_name = CString::new(name)?;
+        let c_value = CString::new(value)?;
+        if !Self::get_all_reader_option_names()
+            .iter()
+            .any(|option| option == name)
+        {
+            return Err(Error::InvalidOptionKey(name.to_string()));
+        }
         unsafe {
             f3d_engine_set_reader_option(c_name.as_ptr(), c_value.as_ptr());
         }
+        crate::reader_options::record(name, value);
+        Ok(())
     }
 
-    pub fn get_lib_info() -> F3DLibInfo {
+    pub fn get_lib_info() -> Result<F3DLibInfo> {
         unsafe {
             let raw_info = f3d_engine_get_lib_info();
             if raw_info.is_null() {
-                panic!("f3d_engine_get_lib_info returned null");
+                return Err(Error::NullHandle("f3d_lib_info_t"));
             }
 
             let info = &*raw_info;
@@ -270,16 +739,16 @@ impl Engine {
                     .into_owned()
             };
 
-            let modules = F3DModuleInfo {
-                name: if (*info.modules).name.is_null() {
-                    String::new()
-                } else {
-                    std::ffi::CStr::from_ptr((*info.modules).name)
-                        .to_string_lossy()
-                        .into_owned()
-                },
-                available: (*info.modules).available != 0,
-            };
+            let mut modules = BTreeMap::new();
+            if !info.modules.is_null() {
+                let mut i = 0;
+                while !(*info.modules.add(i)).name.is_null() {
+                    let module = &*info.modules.add(i);
+                    let name = CStr::from_ptr(module.name).to_string_lossy().into_owned();
+                    modules.insert(name, module.available != 0);
+                    i += 1;
+                }
+            }
 
             let vtk_version = if info.vtk_version.is_null() {
                 String::new()
@@ -313,7 +782,8 @@ impl Engine {
 
             f3d_engine_free_lib_info(raw_info);
 
-            F3DLibInfo {
+            Ok(F3DLibInfo {
+                parsed_version: version.parse().ok(),
                 version,
                 version_full,
                 build_date,
@@ -323,10 +793,15 @@ impl Engine {
                 vtk_version,
                 copyrights: copyrights,
                 license,
-            }
+            })
         }
     }
 
+    /// Whether libf3d was built with an available module, e.g. `"raytracing"`.
+    pub fn has_module(name: &str) -> bool {
+        Self::get_lib_info().is_ok_and(|info| info.has_module(name))
+    }
+
     pub fn get_readers_info() -> Vec<F3DReaderInfo> {
         unsafe {
             let mut count: i32 = 0;
@@ -400,8 +875,8 @@ impl Engine {
                     extensions,
                     mime_types,
                     plugin_name,
-                    has_scene_reader: info.has_scene_reader,
-                    has_geometry_reader: info.has_geometry_reader,
+                    has_scene_reader: info.has_scene_reader != 0,
+                    has_geometry_reader: info.has_geometry_reader != 0,
                 });
 
                 i += 1;
@@ -419,5 +894,154 @@ impl Drop for Engine {
         unsafe {
             f3d_engine_delete(self.ptr.as_ptr());
         }
+        // Only now can libf3d no longer call the command closures.
+        self.commands.clear();
+        if let Some(loader) = self.loader.take() {
+            EXTERNAL_LOADER.with(|current| {
+                let mut current = current.borrow_mut();
+                if current
+                    .as_ref()
+                    .is_some_and(|current| Rc::ptr_eq(current, &loader))
+                {
+                    *current = None;
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(
+            "3.1.0".parse::<F3DVersion>().unwrap(),
+            F3DVersion::new(3, 1, 0)
+        );
+        assert_eq!(
+            "v2.5".parse::<F3DVersion>().unwrap(),
+            F3DVersion::new(2, 5, 0)
+        );
+        let rc = "3.2.0-RC1".parse::<F3DVersion>().unwrap();
+        assert_eq!(rc.pre.as_deref(), Some("RC1"));
+        assert_eq!(rc.to_string(), "3.2.0-RC1");
+        for invalid in ["", "3", "3.x.0", "3.1.0-", "1.2.3.4"] {
+            assert!(matches!(
+                invalid.parse::<F3DVersion>(),
+                Err(Error::InvalidVersion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn orders_versions() {
+        let parse = |v: &str| v.parse::<F3DVersion>().unwrap();
+        assert!(parse("3.2.0-RC1") < parse("3.2.0"));
+        assert!(parse("3.1.9") < parse("3.2.0-RC1"));
+        assert!(parse("10.0.0") > parse("9.9.9"));
+        assert!(parse("3.2.0-RC1") < parse("3.2.0-RC2"));
+        assert!(parse("3.2.0-RC2") < parse("3.2.0-RC10"));
+        assert!(parse("3.2.0-RC10") < parse("3.2.0-RC10.1"));
+        assert!(parse("3.2.0-alpha") < parse("3.2.0-beta"));
+    }
+
+    #[test]
+    fn prioritizes_offscreen_backends() {
+        let names: Vec<String> = EngineBackend::default_priority(true)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "EGL",
+                "OSMesa",
+                "GLX (offscreen)",
+                "WGL (offscreen)",
+                "none"
+            ]
+        );
+        assert_eq!(EngineBackend::Omesa.backend_name(), Some("osmesa"));
+        assert_eq!(EngineBackend::None.backend_name(), None);
+    }
+
+    #[test]
+    fn allows_one_external_engine_per_thread() {
+        let loader: Rc<ProcAddressLoader> = Rc::new(|_: &str| std::ptr::null());
+        EXTERNAL_LOADER.with(|current| current.replace(Some(loader.clone())));
+        let err = Engine::new(
+            EngineBackend::Auto { offscreen: true },
+            EngineContext::External {
+                loader: Box::new(|_: &str| std::ptr::null()),
+            },
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().ends_with("alive on this thread"));
+        let current = EXTERNAL_LOADER.with(|current| current.take()).unwrap();
+        assert!(Rc::ptr_eq(&current, &loader));
+    }
+
+    #[test]
+    fn looks_up_modules_case_insensitively() {
+        let info = F3DLibInfo {
+            version: "3.1.0".to_string(),
+            parsed_version: Some(F3DVersion::new(3, 1, 0)),
+            version_full: String::new(),
+            build_date: String::new(),
+            build_system: String::new(),
+            compiler: String::new(),
+            modules: BTreeMap::from([
+                ("Raytracing".to_string(), true),
+                ("OpenEXR".to_string(), false),
+            ]),
+            vtk_version: String::new(),
+            copyrights: Vec::new(),
+            license: String::new(),
+        };
+        assert!(info.has_module("raytracing"));
+        assert!(!info.has_module("openexr"));
+        assert!(!info.has_module("usd"));
+    }
+
+    #[test]
+    fn chains_plugin_errors_to_the_log_forwarder() {
+        thread_local! {
+            static FORWARDED: RefCell<Vec<f3d_log_verbose_level_t>> =
+                const { RefCell::new(Vec::new()) };
+        }
+        unsafe extern "C" fn forward(level: f3d_log_verbose_level_t, _: *const c_char) {
+            FORWARDED.with(|levels| levels.borrow_mut().push(level));
+        }
+
+        *LOG_FORWARD.lock().unwrap() = Some(forward);
+        unsafe {
+            collect_plugin_error(LOG_ERROR, c"cannot open plugin".as_ptr());
+            collect_plugin_error(LOG_ERROR - 1, c"loading plugin".as_ptr());
+        }
+        *LOG_FORWARD.lock().unwrap() = None;
+
+        assert_eq!(
+            PLUGIN_ERRORS.with(|errors| errors.take()),
+            ["cannot open plugin"]
+        );
+        assert_eq!(
+            FORWARDED.with(|levels| levels.take()),
+            [LOG_ERROR, LOG_ERROR - 1]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_reader_options() {
+        assert!(matches!(
+            Engine::set_reader_option("NoSuchReader.option", "1"),
+            Err(Error::InvalidOptionKey(_))
+        ));
+        assert!(matches!(
+            crate::reader_options::get("NoSuchReader.option"),
+            Err(Error::InvalidOptionKey(_))
+        ));
     }
 }
@@ -0,0 +1,101 @@
+use std::ffi::NulError;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidString(NulError),
+    LoadFailed(String),
+    UnsupportedFile(String),
+    InvalidOptionKey(String),
+    InvalidOptionType {
+        key: String,
+        expected: String,
+    },
+    PluginLoadFailed(String),
+    NullHandle(&'static str),
+    RenderFailed,
+    ImageSaveFailed(String),
+    InvalidConfig(String),
+    InvalidMesh(String),
+    Io(std::io::Error),
+    MeshParse {
+        format: &'static str,
+        reason: String,
+    },
+    InvalidVersion(String),
+    EngineCreationFailed {
+        backend: String,
+        reason: String,
+    },
+    CommandFailed {
+        command: String,
+        reason: String,
+    },
+    InvalidCommand {
+        command: String,
+        reason: String,
+    },
+    InvalidBind(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidString(err) => write!(f, "string contains an interior nul byte: {err}"),
+            Error::LoadFailed(what) => write!(f, "failed to load {what}"),
+            Error::UnsupportedFile(path) => write!(f, "no reader supports {path}"),
+            Error::InvalidOptionKey(key) => write!(f, "unknown option {key}"),
+            Error::InvalidOptionType { key, expected } => {
+                write!(f, "option {key} cannot be accessed as {expected}")
+            }
+            Error::PluginLoadFailed(plugin) => write!(f, "failed to load plugin {plugin}"),
+            Error::NullHandle(what) => write!(f, "libf3d returned a null {what}"),
+            Error::RenderFailed => write!(f, "rendering failed"),
+            Error::ImageSaveFailed(path) => write!(f, "failed to save image to {path}"),
+            Error::InvalidConfig(reason) => write!(f, "invalid configuration: {reason}"),
+            Error::InvalidMesh(reason) => write!(f, "invalid mesh: {reason}"),
+            Error::Io(err) => write!(f, "i/o error: {err}"),
+            Error::MeshParse { format, reason } => write!(f, "invalid {format} data: {reason}"),
+            Error::InvalidVersion(version) => write!(f, "invalid version {version}"),
+            Error::EngineCreationFailed { backend, reason } => {
+                write!(f, "failed to create engine with {backend}: {reason}")
+            }
+            Error::CommandFailed { command, reason } => {
+                write!(f, "command {command} failed: {reason}")
+            }
+            Error::InvalidCommand { command, reason } => {
+                write!(f, "invalid command {command}: {reason}")
+            }
+            Error::InvalidBind(bind) => write!(f, "invalid interaction bind `{bind}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidString(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(err: NulError) -> Self {
+        Error::InvalidString(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// Maps a libf3d status code (non-zero on success) to a `Result`.
+pub(crate) fn check(status: i32, err: impl FnOnce() -> Error) -> Result<()> {
+    if status != 0 { Ok(()) } else { Err(err()) }
+}
@@ -1,4 +1,6 @@
+use crate::error::{Error, Result};
 use crate::sys::*;
+use std::ffi::CString;
 use std::ptr::NonNull;
 
 #[repr(C)]
@@ -45,22 +47,28 @@ impl Image {
         height: u32,
         channel_count: u32,
         channel_type: ImageChannelType,
-    ) -> Option<Self> {
+    ) -> Result<Self> {
         let ptr =
             unsafe { f3d_image_new_params(width, height, channel_count, channel_type as u32) };
 
-        NonNull::new(ptr).map(|ptr| Self { ptr })
+        NonNull::new(ptr)
+            .map(|ptr| Self { ptr })
+            .ok_or(Error::NullHandle("f3d_image_t"))
     }
 
-    pub fn new_empty() -> Option<Self> {
+    pub fn new_empty() -> Result<Self> {
         let ptr = unsafe { f3d_image_new_empty() };
-        NonNull::new(ptr).map(|ptr| Self { ptr })
+        NonNull::new(ptr)
+            .map(|ptr| Self { ptr })
+            .ok_or(Error::NullHandle("f3d_image_t"))
     }
 
-    pub fn new_path(path: &str) -> Option<Self> {
-        let c_path = std::ffi::CString::new(path).expect("CString::new failed");
+    pub fn new_path(path: &str) -> Result<Self> {
+        let c_path = CString::new(path)?;
         let ptr = unsafe { f3d_image_new_path(c_path.as_ptr()) };
-        NonNull::new(ptr).map(|ptr| Self { ptr })
+        NonNull::new(ptr)
+            .map(|ptr| Self { ptr })
+            .ok_or_else(|| Error::LoadFailed(path.to_string()))
     }
 
     pub fn as_ptr(&self) -> *mut f3d_image_t {
@@ -140,11 +148,23 @@ impl Image {
         unsafe { f3d_image_compare(self.ptr.as_ptr(), reference.ptr.as_ptr()) }
     }
 
-    pub fn save(&self, path: &str, format: ImageFormat) {
-        let c_path = std::ffi::CString::new(path).expect("CString::new failed");
+    /// Fails with [`Error::ImageSaveFailed`] when libf3d did not write the file.
+    pub fn save(&self, path: &str, format: ImageFormat) -> Result<()> {
+        let c_path = CString::new(path)?;
+        // libf3d only logs failures, so check that the file was (re)written instead.
+        let modified = || {
+            std::fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+        };
+        let before = modified();
         unsafe {
             f3d_image_save(self.ptr.as_ptr(), c_path.as_ptr(), format as u32);
         }
+        match modified() {
+            Some(after) if Some(after) != before => Ok(()),
+            _ => Err(Error::ImageSaveFailed(path.to_string())),
+        }
     }
 
     pub fn save_to_buffer(&self, format: ImageFormat) -> Vec<u8> {
@@ -178,24 +198,25 @@ impl Image {
         unsafe { std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned() }
     }
 
-    pub fn set_metadata(&mut self, key: &str, value: &str) {
-        let c_key = std::ffi::CString::new(key).expect("CString::new failed");
-        let c_value = std::ffi::CString::new(value).expect("CString::new failed");
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<()> {
+        let c_key = CString::new(key)?;
+        let c_value = CString::new(value)?;
 
         unsafe {
             f3d_image_set_metadata(self.ptr.as_ptr(), c_key.as_ptr(), c_value.as_ptr());
         }
+        Ok(())
     }
 
-    pub fn get_metadata(&self, key: &str) -> Option<String> {
-        let c_key = std::ffi::CString::new(key).expect("CString::new failed");
+    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        let c_key = CString::new(key)?;
 
         let ptr = unsafe { f3d_image_get_metadata(self.ptr.as_ptr(), c_key.as_ptr()) };
         if ptr.is_null() {
-            return None;
+            return Ok(None);
         }
 
-        Some(unsafe { std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned() })
+        Ok(Some(unsafe { std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned() }))
     }
 
     pub fn all_metadata(&self) -> Vec<String> {
@@ -1,140 +1,213 @@
+use crate::error::{Error, Result, check};
 use crate::sys::*;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
+use std::ops::ControlFlow;
 use std::os::raw::{c_char, c_int, c_void};
+use std::panic::AssertUnwindSafe;
 use std::ptr::NonNull;
-
-#[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub enum InteractionBindModifierKeys {
-    InteractionBindAny = 0x80,
-    InteractionBindNone = 0x00,
-    InteractionBindCtrl = 0x01,
-    InteractionBindShift = 0x02,
-    InteractionBindCtrlShft = 0x03,
+use std::rc::Rc;
+
+mod command;
+mod help;
+#[cfg(feature = "keymap")]
+mod keymap;
+pub use command::*;
+pub use help::{BindingHelp, HelpEntry, HelpGroup};
+#[cfg(feature = "keymap")]
+pub use keymap::{BindingConflict, KEYMAP_BINDING_GROUP, Keymap, KeymapEntry};
+
+bitflags::bitflags! {
+    /// Modifier keys of an [`InteractionBind`], with the values of `f3d_interaction_bind_modifier_keys_t`.
+    ///
+    /// Unknown bits coming from libf3d are kept rather than rejected.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct InteractionBindModifiers: u8 {
+        const CTRL = 0x01;
+        const SHIFT = 0x02;
+        /// Matches whatever modifiers are pressed.
+        const ANY = 0x80;
+        const _ = !0;
+    }
 }
 
-impl InteractionBindModifierKeys {
-    fn to_raw(&self) -> u8 {
-        match self {
-            InteractionBindModifierKeys::InteractionBindAny => 0x80,
-            InteractionBindModifierKeys::InteractionBindNone => 0x00,
-            InteractionBindModifierKeys::InteractionBindCtrl => 0x01,
-            InteractionBindModifierKeys::InteractionBindShift => 0x02,
-            InteractionBindModifierKeys::InteractionBindCtrlShft => 0x03,
+impl InteractionBindModifiers {
+    pub const NONE: Self = Self::empty();
+    pub const CTRL_SHIFT: Self = Self::CTRL.union(Self::SHIFT);
+
+    /// Prefix used in bind strings, e.g. `Ctrl+Shift`; empty without modifiers.
+    ///
+    /// Sets libf3d never produces, such as `ANY` with other modifiers or unknown
+    /// bits, are written flag by flag, e.g. `Any+Ctrl` or `0x04`, so that distinct
+    /// modifiers never share a prefix.
+    pub fn prefix(&self) -> String {
+        let mut parts = Vec::new();
+        if self.contains(Self::ANY) {
+            parts.push("Any".to_string());
+        }
+        if self.contains(Self::CTRL) {
+            parts.push("Ctrl".to_string());
         }
+        if self.contains(Self::SHIFT) {
+            parts.push("Shift".to_string());
+        }
+        let unknown = self.difference(Self::all_known());
+        if !unknown.is_empty() {
+            parts.push(format!("{:#04x}", unknown.bits()));
+        }
+        parts.join("+")
     }
 
-    fn from_raw(value: u8) -> Self {
-        match value {
-            0x80 => InteractionBindModifierKeys::InteractionBindAny,
-            0x00 => InteractionBindModifierKeys::InteractionBindNone,
-            0x01 => InteractionBindModifierKeys::InteractionBindCtrl,
-            0x02 => InteractionBindModifierKeys::InteractionBindShift,
-            0x03 => InteractionBindModifierKeys::InteractionBindCtrlShft,
-            _ => panic!("Invalid modifier key value"),
+    fn from_prefix(prefix: &str) -> Option<Self> {
+        if prefix == "None" {
+            return Some(Self::NONE);
         }
+        let mut modifiers = Self::NONE;
+        for part in prefix.split('+') {
+            modifiers |= match part {
+                "Any" => Self::ANY,
+                "Ctrl" => Self::CTRL,
+                "Shift" => Self::SHIFT,
+                _ => {
+                    let bits = u8::from_str_radix(part.strip_prefix("0x")?, 16).ok()?;
+                    Self::from_bits_retain(bits).difference(Self::all_known())
+                }
+            };
+        }
+        Some(modifiers)
+    }
+
+    fn all_known() -> Self {
+        Self::CTRL | Self::SHIFT | Self::ANY
     }
 }
 
+/// A key or mouse interaction with its modifiers, written `Ctrl+O` or `Any+5`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct InteractionBind {
-    pub modifier_keys: InteractionBindModifierKeys,
-    pub interaction_key: [char; 256],
+    /// Compared first, matching libf3d's ordering.
+    pub modifiers: InteractionBindModifiers,
+    /// Key name, e.g. `O`, `Space` or `Escape`.
+    pub interaction: String,
 }
 
-impl InteractionBind {
-    fn from_raw(raw: &f3d_interaction_bind_t) -> Self {
-        unsafe {
-            let mut interaction_key = ['\0'; 256];
+/// Capacity of `f3d_interaction_bind_t::inter`, terminating nul included.
+const INTERACTION_CAPACITY: usize = 256;
 
-            let cstr = std::ffi::CStr::from_ptr(&raw.inter[0]);
-            for (i, ch) in cstr.to_string_lossy().chars().enumerate() {
-                if i >= 256 {
-                    break;
-                }
-                interaction_key[i] = ch;
-            }
+impl InteractionBind {
+    pub fn new(modifiers: InteractionBindModifiers, interaction: impl Into<String>) -> Self {
+        Self {
+            modifiers,
+            interaction: interaction.into(),
+        }
+    }
 
-            Self {
-                modifier_keys: InteractionBindModifierKeys::from_raw(raw.mod_ as u8),
-                interaction_key,
-            }
+    fn from_raw(raw: &f3d_interaction_bind_t) -> Self {
+        let interaction = unsafe { CStr::from_ptr(raw.inter.as_ptr()) };
+        Self {
+            modifiers: InteractionBindModifiers::from_bits_retain(raw.mod_ as u8),
+            interaction: interaction.to_string_lossy().into_owned(),
         }
     }
 
-    fn to_raw(&self) -> f3d_interaction_bind_t {
+    fn to_raw(&self) -> Result<f3d_interaction_bind_t> {
+        let bytes = self.interaction.as_bytes();
+        if bytes.len() >= INTERACTION_CAPACITY || bytes.contains(&0) {
+            return Err(Error::InvalidBind(self.interaction.clone()));
+        }
         let mut raw = f3d_interaction_bind_t {
-            mod_: self.modifier_keys.to_raw() as u32,
-            inter: [0; 256],
+            mod_: self.modifiers.bits() as _,
+            inter: [0; INTERACTION_CAPACITY],
         };
-
-        for (i, ch) in self.interaction_key.iter().enumerate() {
-            if *ch == '\0' {
-                break;
-            }
-            raw.inter[i] = *ch as i8;
+        for (dst, &src) in raw.inter.iter_mut().zip(bytes) {
+            *dst = src as c_char;
         }
-
-        raw
+        Ok(raw)
     }
 
-    pub fn parse(binding_str: &str) -> Self {
-        let cstr = CString::new(binding_str).expect("Failed to create CString from input string");
+    /// Parses `binding_str` with libf3d; [`FromStr`](std::str::FromStr) is the native equivalent.
+    pub fn parse(binding_str: &str) -> Result<Self> {
+        let cstr = CString::new(binding_str)?;
 
         let mut raw = f3d_interaction_bind_t {
             mod_: 0,
-            inter: [0; 256],
+            inter: [0; INTERACTION_CAPACITY],
         };
 
         unsafe {
             f3d_interaction_bind_parse(cstr.as_ptr(), &mut raw as *mut f3d_interaction_bind_t);
         }
 
-        InteractionBind::from_raw(&raw)
+        Ok(InteractionBind::from_raw(&raw))
+    }
+
+    /// Formats with libf3d; [`Display`](std::fmt::Display) is the native equivalent.
+    pub fn format(&self) -> Result<String> {
+        let raw = self.to_raw()?;
+        let mut output = [0 as c_char; 512];
+        unsafe {
+            f3d_interaction_bind_format(&raw, output.as_mut_ptr(), output.len() as i32);
+            Ok(CStr::from_ptr(output.as_ptr())
+                .to_string_lossy()
+                .into_owned())
+        }
     }
 }
 
 impl std::fmt::Display for InteractionBind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        unsafe {
-            let raw = self.to_raw();
-            let mut output = [0u8; 512];
-
-            f3d_interaction_bind_format(
-                &raw as *const f3d_interaction_bind_t,
-                output.as_mut_ptr() as *mut i8,
-                output.len() as i32,
-            );
-
-            let s = std::ffi::CStr::from_ptr(output.as_ptr() as *const i8).to_string_lossy();
+        match self.modifiers.prefix().as_str() {
+            "" => write!(f, "{}", self.interaction),
+            prefix => write!(f, "{prefix}+{}", self.interaction),
+        }
+    }
+}
 
-            write!(f, "{s}")
+impl std::str::FromStr for InteractionBind {
+    type Err = Error;
+
+    /// Splits at the last `+`, like libf3d; unknown modifiers are an error instead of ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let (modifiers, interaction) = match s.rsplit_once('+') {
+            Some((prefix, interaction)) => (
+                InteractionBindModifiers::from_prefix(prefix)
+                    .ok_or_else(|| Error::InvalidBind(s.to_string()))?,
+                interaction,
+            ),
+            None => (InteractionBindModifiers::NONE, s),
+        };
+        if interaction.is_empty() {
+            return Err(Error::InvalidBind(s.to_string()));
         }
+        let bind = Self::new(modifiers, interaction);
+        bind.to_raw()?;
+        Ok(bind)
     }
 }
 
-impl PartialEq for InteractionBind {
-    fn eq(&self, other: &Self) -> bool {
-        unsafe { f3d_interaction_bind_equals(&self.to_raw(), &other.to_raw()) == 1 }
+#[cfg(feature = "serde")]
+impl serde::Serialize for InteractionBind {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
-impl PartialOrd for InteractionBind {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        unsafe {
-            let lhs = self.to_raw();
-            let rhs = other.to_raw();
-
-            if f3d_interaction_bind_less_than(&lhs, &rhs) == 1 {
-                Some(std::cmp::Ordering::Less)
-            } else if f3d_interaction_bind_equals(&lhs, &rhs) == 1 {
-                Some(std::cmp::Ordering::Equal)
-            } else {
-                Some(std::cmp::Ordering::Greater)
-            }
-        }
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InteractionBind {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let bind = String::deserialize(deserializer)?;
+        bind.parse().map_err(serde::de::Error::custom)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InteractorBindType {
     InteractorBindingCyclic = 0,
     InteractorBindingNumerical = 1,
@@ -143,7 +216,7 @@ pub enum InteractorBindType {
 }
 
 impl InteractorBindType {
-    fn to_raw(&self) -> u32 {
+    fn to_raw(self) -> u32 {
         match self {
             InteractorBindType::InteractorBindingCyclic => 0,
             InteractorBindType::InteractorBindingNumerical => 1,
@@ -161,6 +234,36 @@ impl InteractorBindType {
             _ => panic!("Invalid InteractorBindType value"),
         }
     }
+
+    /// Lowercase name used in keymaps, e.g. `toggle`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            InteractorBindType::InteractorBindingCyclic => "cyclic",
+            InteractorBindType::InteractorBindingNumerical => "numerical",
+            InteractorBindType::InteractorBindingToggle => "toggle",
+            InteractorBindType::InteractorBindingOther => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for InteractorBindType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for InteractorBindType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "cyclic" => Ok(InteractorBindType::InteractorBindingCyclic),
+            "numerical" => Ok(InteractorBindType::InteractorBindingNumerical),
+            "toggle" => Ok(InteractorBindType::InteractorBindingToggle),
+            "other" => Ok(InteractorBindType::InteractorBindingOther),
+            _ => Err(Error::InvalidConfig(format!("unknown binding type `{s}`"))),
+        }
+    }
 }
 
 pub enum InteractorMouseButton {
@@ -288,9 +391,11 @@ impl InteractorAnimationDirection {
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct BindingDocumentation {
-    doc: String,
-    value: String,
+    pub doc: String,
+    /// Current value of what the binding changes, e.g. `ON`; empty when not applicable.
+    pub value: String,
 }
 
 impl BindingDocumentation {
@@ -333,14 +438,137 @@ impl BindingDocumentation {
     }
 }
 
-pub struct Interactor {
+/// Callback and panic slot shared with [`Interactor::start_callback`] while the loop runs.
+struct StartState<'c> {
+    interactor: Interactor<'c>,
+    callback: &'c mut dyn FnMut(&Interactor) -> ControlFlow<()>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+/// Outcome of a command callback, converted from the closure's return value.
+pub trait CommandOutput {
+    fn into_outcome(self) -> std::result::Result<(), String>;
+}
+
+impl CommandOutput for () {
+    fn into_outcome(self) -> std::result::Result<(), String> {
+        Ok(())
+    }
+}
+
+impl CommandOutput for bool {
+    fn into_outcome(self) -> std::result::Result<(), String> {
+        if self {
+            Ok(())
+        } else {
+            Err("command returned false".to_string())
+        }
+    }
+}
+
+impl<E: std::fmt::Display> CommandOutput for std::result::Result<(), E> {
+    fn into_outcome(self) -> std::result::Result<(), String> {
+        self.map_err(|err| err.to_string())
+    }
+}
+
+type CommandCallback = dyn FnMut(&[String]) -> std::result::Result<(), String>;
+
+/// Closure registered with libf3d, pointed to by the callback user data.
+///
+/// The callback is only ever borrowed through the `RefCell`, so a command that triggers
+/// itself fails instead of aliasing its closure.
+struct CommandState {
+    action: String,
+    callback: RefCell<Box<CommandCallback>>,
+    /// Set when the command is removed while the callback runs; libf3d then forgets it
+    /// once the callback returns.
+    pending_removal: Cell<Option<NonNull<f3d_interactor_t>>>,
+}
+
+impl CommandState {
+    fn is_running(&self) -> bool {
+        self.callback.try_borrow_mut().is_err()
+    }
+}
+
+thread_local! {
+    /// Failure of the last command callback, read back by [`Interactor::trigger_command`]
+    /// since libf3d command callbacks cannot report one.
+    static COMMAND_FAILURE: RefCell<Option<Error>> = const { RefCell::new(None) };
+}
+
+/// Closures of the commands added to an engine, keyed by action.
+///
+/// The registry owns them until their command is removed or the engine drops, so libf3d
+/// never calls a freed closure, even when a [`CommandHandle`] is leaked. The id tells
+/// which handle owns each action, so a stale handle does not remove its replacement.
+#[derive(Default)]
+pub(crate) struct CommandRegistry {
+    next_id: Cell<u64>,
+    owners: RefCell<HashMap<String, (u64, Rc<CommandState>)>>,
+    /// Documentation of bindings added through this crate, which the C API cannot store.
+    binding_docs: RefCell<HashMap<InteractionBind, String>>,
+}
+
+impl CommandRegistry {
+    /// Frees every closure; libf3d must no longer be able to call them.
+    pub(crate) fn clear(&self) {
+        let owners = std::mem::take(&mut *self.owners.borrow_mut());
+        drop(owners);
+    }
+}
+
+/// A command added with [`Interactor::add_command`].
+///
+/// Dropping the handle removes the command from the interactor and frees the closure.
+/// A leaked handle leaves the command registered until the engine drops.
+#[must_use = "the command is removed as soon as the handle is dropped"]
+pub struct CommandHandle<'a> {
+    interactor: Interactor<'a>,
+    id: u64,
+    state: Rc<CommandState>,
+}
+
+impl CommandHandle<'_> {
+    pub fn action(&self) -> &str {
+        &self.state.action
+    }
+
+    /// Whether the command is still registered through this handle.
+    pub fn is_active(&self) -> bool {
+        self.interactor.owns_command(&self.state.action, self.id)
+    }
+
+    /// Removes the command now instead of when the handle is dropped.
+    pub fn remove(self) {
+        drop(self);
+    }
+}
+
+impl Drop for CommandHandle<'_> {
+    fn drop(&mut self) {
+        if self.is_active() {
+            let _ = self.interactor.remove_command(&self.state.action);
+        }
+    }
+}
+
+/// Handle to the interactor of an [`Engine`](crate::engine::Engine), valid for the engine's lifetime.
+#[derive(Clone, Copy)]
+pub struct Interactor<'a> {
     ptr: NonNull<f3d_interactor_t>,
+    commands: &'a CommandRegistry,
 }
 
-impl Interactor {
-    pub(crate) unsafe fn from_raw(ptr: *mut f3d_interactor_t) -> Self {
+impl<'a> Interactor<'a> {
+    pub(crate) unsafe fn from_raw(
+        ptr: *mut f3d_interactor_t,
+        commands: &'a CommandRegistry,
+    ) -> Self {
         Self {
             ptr: NonNull::new(ptr).expect("null f3d_interactor_t"),
+            commands,
         }
     }
 
@@ -355,48 +583,130 @@ impl Interactor {
         arg_count: c_int,
         user_data: *mut c_void,
     ) {
-        unsafe {
-            let args_slice = std::slice::from_raw_parts(args, arg_count as usize);
-
-            let args: Vec<String> = args_slice
+        let args: Vec<String> = if args.is_null() {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(args, arg_count.max(0) as usize) }
                 .iter()
-                .map(|&arg| CStr::from_ptr(arg).to_string_lossy().into_owned())
-                .collect();
+                .map(|&arg| {
+                    unsafe { CStr::from_ptr(arg) }
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+        // Keep the state alive until the callback returns, even if it drops its own handle.
+        let state = unsafe {
+            Rc::increment_strong_count(user_data as *const CommandState);
+            Rc::from_raw(user_data as *const CommandState)
+        };
 
-            let closure = &*(user_data as *mut Box<dyn Fn(&[String]) + Send>);
-            closure(&args);
+        let outcome = match state.callback.try_borrow_mut() {
+            // Unwinding through libf3d is undefined behavior, so panics become failures.
+            Ok(mut callback) => std::panic::catch_unwind(AssertUnwindSafe(|| (*callback)(&args)))
+                .unwrap_or_else(|payload| Err(panic_message(payload.as_ref()))),
+            Err(_) => Err("the command is already running".to_string()),
+        };
+        if let Some(interactor) = state.pending_removal.take()
+            && let Ok(action) = CString::new(state.action.as_str())
+        {
+            unsafe { f3d_interactor_remove_command(interactor.as_ptr(), action.as_ptr()) };
+        }
+        if let Err(reason) = outcome {
+            COMMAND_FAILURE.with(|failure| {
+                *failure.borrow_mut() = Some(Error::CommandFailed {
+                    command: state.action.clone(),
+                    reason,
+                })
+            });
         }
     }
 
-    pub fn add_command<F>(&self, action: &str, callback: F)
+    /// Adds or replaces the command handling `action`.
+    ///
+    /// `callback` may return `()`, `bool` or a `Result`; a failure, or a panic, makes
+    /// [`trigger_command`](Self::trigger_command) return [`Error::CommandFailed`].
+    ///
+    /// The closure is owned by the engine until the command is removed, so it cannot
+    /// borrow anything that may be dropped first:
+    ///
+    /// ```compile_fail,E0597
+    /// # use f3d::engine::{Engine, EngineBackend, EngineContext};
+    /// let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+    /// let interactor = engine.interactor();
+    /// {
+    ///     let mut count = 0;
+    ///     std::mem::forget(interactor.add_command("count", |_: &[String]| count += 1));
+    /// }
+    /// interactor.trigger_command("count".to_string(), false).unwrap();
+    /// ```
+    pub fn add_command<F, R>(&self, action: &str, mut callback: F) -> Result<CommandHandle<'a>>
     where
-        F: Fn(&[String]) + Send + 'static,
-        {
-            let action_cstr = CString::new(action).expect("Invalid action");
-
-            // Box the closure and leak it
-            let boxed: Box<Box<dyn Fn(&[String]) + Send>> = Box::new(Box::new(callback));
-
-            let user_data = Box::into_raw(boxed) as *mut c_void;
-
-            unsafe {
-                f3d_interactor_add_command(
-                    self.ptr.as_ptr(),
-                    action_cstr.as_ptr(),
-                    Some(
-                        Self::command_callback
+        F: FnMut(&[String]) -> R + 'static,
+        R: CommandOutput,
+    {
+        let action_cstr = CString::new(action)?;
+        let state = Rc::new(CommandState {
+            action: action.to_string(),
+            callback: RefCell::new(Box::new(move |args| callback(args).into_outcome())),
+            pending_removal: Cell::new(None),
+        });
+
+        let id = self.commands.next_id.get();
+        self.commands.next_id.set(id + 1);
+        unsafe {
+            f3d_interactor_add_command(
+                self.ptr.as_ptr(),
+                action_cstr.as_ptr(),
+                Some(
+                    Self::command_callback
                         as unsafe extern "C" fn(*mut *const c_char, c_int, *mut c_void),
-                    ),
-                    user_data,
-                );
-            }
+                ),
+                Rc::as_ptr(&state) as *mut c_void,
+            );
         }
+        let replaced = self
+            .commands
+            .owners
+            .borrow_mut()
+            .insert(action.to_string(), (id, state.clone()));
+        if let Some((_, replaced)) = &replaced {
+            // libf3d already replaced it, so a deferred removal would remove this command.
+            replaced.pending_removal.set(None);
+        }
+        // Dropped outside the borrow, since the closure may own handles of other commands.
+        drop(replaced);
+
+        Ok(CommandHandle {
+            interactor: *self,
+            id,
+            state,
+        })
+    }
 
-    pub fn remove_command(&self, action: &str) {
-        let action_cstr = CString::new(action).expect("Invalid action");
-        unsafe {
-            f3d_interactor_remove_command(self.ptr.as_ptr(), action_cstr.as_ptr());
+    /// Removes a command and frees its closure.
+    ///
+    /// A command removing itself stays known to libf3d until its callback returns.
+    pub fn remove_command(&self, action: &str) -> Result<()> {
+        let action_cstr = CString::new(action)?;
+        let removed = self.commands.owners.borrow_mut().remove(action);
+        match &removed {
+            Some((_, state)) if state.is_running() => state.pending_removal.set(Some(self.ptr)),
+            _ => unsafe {
+                f3d_interactor_remove_command(self.ptr.as_ptr(), action_cstr.as_ptr());
+            },
         }
+        // Dropped outside the borrow, since the closure may own handles of other commands.
+        drop(removed);
+        Ok(())
+    }
+
+    fn owns_command(&self, action: &str, id: u64) -> bool {
+        self.commands
+            .owners
+            .borrow()
+            .get(action)
+            .is_some_and(|(owner, _)| *owner == id)
     }
 
     pub fn get_command_actions(&self) -> Vec<String> {
@@ -417,15 +727,23 @@ impl Interactor {
         }
     }
 
-    pub fn trigger_command(&self, command: String, keep_comments: bool) {
-        let ccommand = CString::new(command).unwrap();
-        unsafe {
+    pub fn trigger_command(&self, command: String, keep_comments: bool) -> Result<()> {
+        let ccommand = CString::new(command.as_str())?;
+        COMMAND_FAILURE.with(|failure| failure.borrow_mut().take());
+        let status = unsafe {
             f3d_interactor_trigger_command(
                 self.ptr.as_ptr(),
                 ccommand.as_ptr(),
                 keep_comments as i32,
-            );
+            )
+        };
+        if let Some(err) = COMMAND_FAILURE.with(|failure| failure.borrow_mut().take()) {
+            return Err(err);
         }
+        check(status, || Error::CommandFailed {
+            reason: "rejected by libf3d".to_string(),
+            command,
+        })
     }
 
     pub fn init_bindings(&self) {
@@ -434,14 +752,22 @@ impl Interactor {
         }
     }
 
-    pub fn add_binding(&self, bind: InteractionBind, commands: Vec<String>, group: Option<&str>, bind_type: InteractorBindType, notify: i32) {
-        let bind_raw = bind.to_raw();
-        let commands_cstr: Vec<CString> = commands
+    pub fn add_binding(
+        &self,
+        bind: InteractionBind,
+        commands: Vec<String>,
+        group: Option<&str>,
+        bind_type: InteractorBindType,
+        notify: i32,
+    ) -> Result<()> {
+        let bind_raw = bind.to_raw()?;
+        let commands_cstr = commands
             .iter()
-            .map(|cmd| CString::new(cmd.as_str()).expect("Failed to create CString"))
-            .collect();
+            .map(|cmd| CString::new(cmd.as_str()))
+            .collect::<std::result::Result<Vec<CString>, _>>()?;
         let commands_ptrs: Vec<*const i8> = commands_cstr.iter().map(|cmd| cmd.as_ptr()).collect();
-        let cgroup = group.map(|g| CString::new(g).expect("Failed to create CString for group"));
+        let cgroup = group.map(CString::new).transpose()?;
+        self.commands.binding_docs.borrow_mut().remove(&bind);
 
         unsafe {
             f3d_interactor_add_binding(
@@ -449,25 +775,28 @@ impl Interactor {
                 &bind_raw as *const f3d_interaction_bind_t,
                 commands_ptrs.as_ptr() as *mut *const i8,
                 commands_ptrs.len() as i32,
-                cgroup.map_or(std::ptr::null(), |cg| cg.as_ptr()),
-                bind_type as f3d_interactor_binding_type_t,
-                notify
+                cgroup.as_ref().map_or(std::ptr::null(), |cg| cg.as_ptr()),
+                bind_type.to_raw(),
+                notify,
             );
         }
+        Ok(())
     }
 
-    pub fn remove_binding(&self, bind: InteractionBind) {
-        let bind_raw = bind.to_raw();
+    pub fn remove_binding(&self, bind: InteractionBind) -> Result<()> {
+        let bind_raw = bind.to_raw()?;
+        self.commands.binding_docs.borrow_mut().remove(&bind);
         unsafe {
             f3d_interactor_remove_binding(
                 self.ptr.as_ptr(),
                 &bind_raw as *const f3d_interaction_bind_t,
             );
         }
+        Ok(())
     }
 
-    pub fn get_bindings_for_group(&self, group: &str) -> Vec<InteractionBind> {
-        let cgroup = CString::new(group).expect("Failed to create CString for group");
+    pub fn get_bindings_for_group(&self, group: &str) -> Result<Vec<InteractionBind>> {
+        let cgroup = CString::new(group)?;
         let mut count: i32 = 0;
         unsafe {
             let raw_bindings = f3d_interactor_get_binds_for_group(
@@ -484,13 +813,55 @@ impl Interactor {
 
             f3d_interactor_free_bind_array(raw_bindings);
 
-            bindings
+            Ok(bindings)
         }
     }
 
-    pub fn get_binding_documentation(&self, bind: InteractionBind) -> BindingDocumentation {
-        let bind_raw = bind.to_raw();
+    /// Binding groups in libf3d order, e.g. `Camera` or `Scene`.
+    pub fn get_bind_groups(&self) -> Vec<String> {
+        let mut count: i32 = 0;
         unsafe {
+            let raw_groups = f3d_interactor_get_bind_groups(self.ptr.as_ptr(), &mut count);
+
+            let mut groups = Vec::new();
+            for i in 0..count {
+                let cstr = CStr::from_ptr(*raw_groups.add(i as usize));
+                groups.push(cstr.to_string_lossy().into_owned());
+            }
+
+            f3d_interactor_free_string_array(raw_groups, count);
+
+            groups
+        }
+    }
+
+    /// Every binding with its group, groups in libf3d order.
+    pub fn all_bindings(&self) -> Result<Vec<(String, InteractionBind)>> {
+        let mut bindings = Vec::new();
+        for group in self.get_bind_groups() {
+            for bind in self.get_bindings_for_group(&group)? {
+                bindings.push((group.clone(), bind));
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// Documents a binding added with [`add_binding`](Self::add_binding), whose C API takes
+    /// no documentation. It is reported by
+    /// [`get_binding_documentation`](Self::get_binding_documentation) until the binding is
+    /// removed or replaced.
+    pub fn set_binding_documentation(&self, bind: InteractionBind, doc: &str) -> Result<()> {
+        bind.to_raw()?;
+        self.commands
+            .binding_docs
+            .borrow_mut()
+            .insert(bind, doc.to_string());
+        Ok(())
+    }
+
+    pub fn get_binding_documentation(&self, bind: InteractionBind) -> Result<BindingDocumentation> {
+        let bind_raw = bind.to_raw()?;
+        let mut documentation = unsafe {
             let mut raw_doc = f3d_binding_documentation_t {
                 doc: [0; 512],
                 value: [0; 256],
@@ -503,19 +874,23 @@ impl Interactor {
             );
 
             BindingDocumentation::from_raw(&raw_doc)
+        };
+        if let Some(doc) = self.commands.binding_docs.borrow().get(&bind) {
+            documentation.doc = doc.clone();
         }
+        Ok(documentation)
     }
 
-    pub fn get_binding_type(&self, bind: InteractionBind) -> InteractorBindType {
-        let bind_raw = bind.to_raw();
-        unsafe {
+    pub fn get_binding_type(&self, bind: InteractionBind) -> Result<InteractorBindType> {
+        let bind_raw = bind.to_raw()?;
+        Ok(unsafe {
             let raw_type = f3d_interactor_get_binding_type(
                 self.ptr.as_ptr(),
                 &bind_raw as *const f3d_interaction_bind_t,
             );
 
             InteractorBindType::from_raw(raw_type)
-        }
+        })
     }
 
     pub fn toggle_animation(&self, direction: InteractorAnimationDirection) {
@@ -613,18 +988,19 @@ impl Interactor {
         }
     }
 
-    pub fn play_interaction(&self, file_path: &str, delta_time: f64) {
-        let cfile_path = CString::new(file_path).unwrap();
-        unsafe {
-            f3d_interactor_play_interaction(self.ptr.as_ptr(), cfile_path.as_ptr(), delta_time);
-        }
+    pub fn play_interaction(&self, file_path: &str, delta_time: f64) -> Result<()> {
+        let cfile_path = CString::new(file_path)?;
+        let status = unsafe {
+            f3d_interactor_play_interaction(self.ptr.as_ptr(), cfile_path.as_ptr(), delta_time)
+        };
+        check(status, || Error::LoadFailed(file_path.to_string()))
     }
 
-    pub fn record_interaction(&self, file_path: &str) {
-        let cfile_path = CString::new(file_path).unwrap();
-        unsafe {
-            f3d_interactor_record_interaction(self.ptr.as_ptr(), cfile_path.as_ptr());
-        }
+    pub fn record_interaction(&self, file_path: &str) -> Result<()> {
+        let cfile_path = CString::new(file_path)?;
+        let status =
+            unsafe { f3d_interactor_record_interaction(self.ptr.as_ptr(), cfile_path.as_ptr()) };
+        check(status, || Error::LoadFailed(file_path.to_string()))
     }
 
     pub fn start(&self, framerate: f64) {
@@ -633,7 +1009,48 @@ impl Interactor {
         }
     }
 
-    //TODO: Start with callback
+    /// Like [`start`](Self::start), calling `callback` on every event loop tick until it
+    /// returns [`ControlFlow::Break`].
+    ///
+    /// A panic in `callback` stops the interactor and resumes once `start` returns.
+    pub fn start_with_callback<F>(&self, framerate: f64, mut callback: F)
+    where
+        F: FnMut(&Interactor) -> ControlFlow<()>,
+    {
+        let mut state = StartState {
+            interactor: *self,
+            callback: &mut callback,
+            panic: None,
+        };
+        unsafe {
+            f3d_interactor_start_with_callback(
+                self.ptr.as_ptr(),
+                framerate,
+                Some(Self::start_callback as unsafe extern "C" fn(*mut c_void)),
+                &mut state as *mut StartState as *mut c_void,
+            );
+        }
+        if let Some(payload) = state.panic {
+            std::panic::resume_unwind(payload);
+        }
+    }
+
+    extern "C" fn start_callback(user_data: *mut c_void) {
+        let state = unsafe { &mut *(user_data as *mut StartState) };
+        if state.panic.is_some() {
+            return;
+        }
+        let interactor = state.interactor;
+        let callback = &mut state.callback;
+        match std::panic::catch_unwind(AssertUnwindSafe(|| callback(&interactor))) {
+            Ok(ControlFlow::Continue(())) => {}
+            Ok(ControlFlow::Break(())) => interactor.request_stop(),
+            Err(payload) => {
+                state.panic = Some(payload);
+                interactor.request_stop();
+            }
+        }
+    }
 
     pub fn stop(&self) {
         unsafe {
@@ -653,3 +1070,250 @@ impl Interactor {
         }
     }
 }
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => format!("panicked: {message}"),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => format!("panicked: {message}"),
+            None => "panicked".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODIFIERS: [InteractionBindModifiers; 5] = [
+        InteractionBindModifiers::NONE,
+        InteractionBindModifiers::CTRL,
+        InteractionBindModifiers::SHIFT,
+        InteractionBindModifiers::CTRL_SHIFT,
+        InteractionBindModifiers::ANY,
+    ];
+    const INTERACTIONS: [&str; 9] = [
+        "A", "z", "5", "Space", "Escape", "F12", "Left", "KP_Add", "Question",
+    ];
+
+    fn binds() -> Vec<InteractionBind> {
+        MODIFIERS
+            .iter()
+            .flat_map(|&modifiers| {
+                INTERACTIONS
+                    .iter()
+                    .map(move |&interaction| InteractionBind::new(modifiers, interaction))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_bind_strings() {
+        for bind in binds() {
+            assert_eq!(bind.to_string().parse::<InteractionBind>().unwrap(), bind);
+        }
+        let bind: InteractionBind = "Ctrl+Shift+O".parse().unwrap();
+        assert_eq!(bind.modifiers, InteractionBindModifiers::CTRL_SHIFT);
+        assert_eq!(bind.interaction, "O");
+        assert_eq!(
+            "None+O".parse::<InteractionBind>().unwrap().to_string(),
+            "O"
+        );
+        assert_eq!(
+            "Any+é"
+                .parse::<InteractionBind>()
+                .unwrap()
+                .to_raw()
+                .unwrap()
+                .inter[..3],
+            [0xc3u8 as c_char, 0xa9u8 as c_char, 0]
+        );
+
+        assert!("Alt+O".parse::<InteractionBind>().is_err());
+        assert!("Ctrl+".parse::<InteractionBind>().is_err());
+        assert!(
+            "X".repeat(INTERACTION_CAPACITY)
+                .parse::<InteractionBind>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_modifier_bits() {
+        let raw = f3d_interaction_bind_t {
+            mod_: 0x04,
+            inter: [0; INTERACTION_CAPACITY],
+        };
+        let bind = InteractionBind::from_raw(&raw);
+        assert_eq!(bind.modifiers.bits(), 0x04);
+        assert_eq!(bind.to_raw().unwrap().mod_, 0x04);
+
+        let any_ctrl = InteractionBindModifiers::ANY | InteractionBindModifiers::CTRL;
+        let odd = [
+            (
+                InteractionBind::new(InteractionBindModifiers::from_bits_retain(0x04), "O"),
+                "0x04+O",
+            ),
+            (InteractionBind::new(any_ctrl, "O"), "Any+Ctrl+O"),
+            (
+                InteractionBind::new(InteractionBindModifiers::from_bits_retain(0x45), "O"),
+                "Ctrl+0x44+O",
+            ),
+        ];
+        for (bind, formatted) in odd {
+            assert_eq!(bind.to_string(), formatted);
+            assert_eq!(formatted.parse::<InteractionBind>().unwrap(), bind);
+        }
+        assert!("0x+O".parse::<InteractionBind>().is_err());
+        assert!("Ctrl+Alt+O".parse::<InteractionBind>().is_err());
+    }
+
+    #[test]
+    fn orders_by_modifiers_then_interaction() {
+        let mut sorted = binds();
+        sorted.sort();
+        assert_eq!(
+            sorted[0],
+            InteractionBind::new(InteractionBindModifiers::NONE, "5")
+        );
+        assert_eq!(
+            sorted.last().unwrap(),
+            &InteractionBind::new(InteractionBindModifiers::ANY, "z")
+        );
+        let unique: std::collections::HashSet<_> = binds().into_iter().collect();
+        assert_eq!(unique.len(), MODIFIERS.len() * INTERACTIONS.len());
+    }
+
+    #[test]
+    fn matches_libf3d_format_and_parse() {
+        let binds = binds();
+        for bind in &binds {
+            let formatted = bind.format().unwrap();
+            assert_eq!(formatted, bind.to_string());
+            assert_eq!(&InteractionBind::parse(&formatted).unwrap(), bind);
+            assert_eq!(&formatted.parse::<InteractionBind>().unwrap(), bind);
+        }
+        for lhs in &binds {
+            for rhs in &binds {
+                let (lhs_raw, rhs_raw) = (lhs.to_raw().unwrap(), rhs.to_raw().unwrap());
+                let equals = unsafe { f3d_interaction_bind_equals(&lhs_raw, &rhs_raw) } == 1;
+                let less = unsafe { f3d_interaction_bind_less_than(&lhs_raw, &rhs_raw) } == 1;
+                assert_eq!(equals, lhs == rhs, "{lhs} == {rhs}");
+                assert_eq!(less, lhs < rhs, "{lhs} < {rhs}");
+            }
+        }
+    }
+
+    fn call(state: &Rc<CommandState>, args: &[&str]) -> Option<Error> {
+        let args: Vec<CString> = args.iter().map(|arg| CString::new(*arg).unwrap()).collect();
+        let mut ptrs: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+        COMMAND_FAILURE.with(|failure| failure.borrow_mut().take());
+        Interactor::command_callback(
+            ptrs.as_mut_ptr(),
+            ptrs.len() as c_int,
+            Rc::as_ptr(state) as *mut c_void,
+        );
+        COMMAND_FAILURE.with(|failure| failure.borrow_mut().take())
+    }
+
+    fn state<R: CommandOutput>(
+        action: &str,
+        mut callback: impl FnMut(&[String]) -> R + 'static,
+    ) -> Rc<CommandState> {
+        Rc::new(CommandState {
+            action: action.to_string(),
+            callback: RefCell::new(Box::new(move |args| callback(args).into_outcome())),
+            pending_removal: Cell::new(None),
+        })
+    }
+
+    #[test]
+    fn passes_args_to_mutable_callbacks() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let collected = seen.clone();
+        let command = state("collect", move |args: &[String]| {
+            collected.borrow_mut().extend_from_slice(args)
+        });
+        assert!(call(&command, &["a", "b c"]).is_none());
+        assert!(call(&command, &[]).is_none());
+        drop(command);
+        assert_eq!(*seen.borrow(), ["a", "b c"]);
+    }
+
+    #[test]
+    fn reports_failures_and_panics() {
+        let command = state("check", |args: &[String]| !args.is_empty());
+        assert!(call(&command, &["x"]).is_none());
+        assert!(matches!(
+            call(&command, &[]),
+            Some(Error::CommandFailed { command, .. }) if command == "check"
+        ));
+
+        let command = state("parse", |args: &[String]| {
+            args[0].parse::<i32>().map(|_| ())
+        });
+        let err = call(&command, &["nan"]).unwrap();
+        assert_eq!(
+            err.to_string(),
+            "command parse failed: invalid digit found in string"
+        );
+
+        let command = state("boom", |_: &[String]| -> () { panic!("oops") });
+        let err = call(&command, &[]).unwrap();
+        assert_eq!(err.to_string(), "command boom failed: panicked: oops");
+    }
+
+    #[test]
+    fn rejects_reentrant_calls() {
+        let command = state("again", |_: &[String]| {});
+        let running = command.callback.borrow_mut();
+        let err = call(&command, &[]).unwrap();
+        assert_eq!(
+            err.to_string(),
+            "command again failed: the command is already running"
+        );
+        drop(running);
+        assert!(call(&command, &[]).is_none());
+        assert_eq!(Rc::strong_count(&command), 1);
+    }
+
+    #[test]
+    fn frees_closures_on_removal_and_engine_drop() {
+        use crate::engine::{Engine, EngineBackend, EngineContext};
+
+        let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+        let interactor = engine.interactor();
+        let calls = Rc::new(Cell::new(0));
+
+        let counter = calls.clone();
+        std::mem::forget(
+            interactor
+                .add_command("leaked", move |_: &[String]| counter.set(counter.get() + 1))
+                .unwrap(),
+        );
+        interactor
+            .trigger_command("leaked".to_string(), false)
+            .unwrap();
+        assert_eq!(calls.get(), 1);
+
+        let counter = calls.clone();
+        std::mem::forget(
+            interactor
+                .add_command("removed", move |_: &[String]| {
+                    counter.set(counter.get() + 1)
+                })
+                .unwrap(),
+        );
+        assert_eq!(Rc::strong_count(&calls), 3);
+        interactor.remove_command("removed").unwrap();
+        assert_eq!(Rc::strong_count(&calls), 2);
+        assert!(
+            interactor
+                .trigger_command("removed".to_string(), false)
+                .is_err()
+        );
+
+        drop(engine);
+        assert_eq!(Rc::strong_count(&calls), 1);
+    }
+}
@@ -0,0 +1,500 @@
+//! Typed interactor commands declared with [`f3d_command!`](crate::f3d_command).
+//!
+//! A command struct knows how to parse the arguments libf3d hands to its
+//! callback and how to format itself back into a command line, so the same
+//! type drives [`Interactor::add_typed_command`] and [`Interactor::trigger`].
+
+use super::{CommandHandle, CommandOutput, Interactor};
+use crate::error::{Error, Result};
+use crate::options::OptionType;
+use std::path::PathBuf;
+
+/// A Rust type that a command argument can be parsed as and formatted from.
+pub trait CommandArg: Sized {
+    const TYPE: OptionType;
+    /// Whether the argument may be left out; only trailing arguments should be.
+    const OPTIONAL: bool = false;
+
+    fn parse_arg(arg: &str) -> Option<Self>;
+
+    /// Value of an argument that was left out.
+    fn missing() -> Option<Self> {
+        None
+    }
+
+    /// `None` when the argument is left out of the command line.
+    fn format_arg(&self) -> Option<String>;
+
+    /// Values offered for completion.
+    fn candidates() -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl CommandArg for bool {
+    const TYPE: OptionType = OptionType::Bool;
+
+    fn parse_arg(arg: &str) -> Option<Self> {
+        match arg.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Some(true),
+            "false" | "0" | "no" | "off" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn format_arg(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+
+    fn candidates() -> Vec<String> {
+        vec!["true".to_string(), "false".to_string()]
+    }
+}
+
+impl CommandArg for i32 {
+    const TYPE: OptionType = OptionType::Int;
+
+    fn parse_arg(arg: &str) -> Option<Self> {
+        arg.parse().ok()
+    }
+
+    fn format_arg(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl CommandArg for f64 {
+    const TYPE: OptionType = OptionType::Double;
+
+    fn parse_arg(arg: &str) -> Option<Self> {
+        arg.parse().ok()
+    }
+
+    fn format_arg(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl CommandArg for String {
+    const TYPE: OptionType = OptionType::String;
+
+    fn parse_arg(arg: &str) -> Option<Self> {
+        Some(arg.to_string())
+    }
+
+    fn format_arg(&self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl CommandArg for PathBuf {
+    const TYPE: OptionType = OptionType::Path;
+
+    fn parse_arg(arg: &str) -> Option<Self> {
+        Some(PathBuf::from(arg))
+    }
+
+    fn format_arg(&self) -> Option<String> {
+        Some(self.to_string_lossy().into_owned())
+    }
+}
+
+impl<T: CommandArg> CommandArg for Option<T> {
+    const TYPE: OptionType = T::TYPE;
+    const OPTIONAL: bool = true;
+
+    fn parse_arg(arg: &str) -> Option<Self> {
+        T::parse_arg(arg).map(Some)
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+
+    fn format_arg(&self) -> Option<String> {
+        self.as_ref().and_then(T::format_arg)
+    }
+
+    fn candidates() -> Vec<String> {
+        T::candidates()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgInfo {
+    pub name: &'static str,
+    pub value_type: OptionType,
+    pub optional: bool,
+    pub help: String,
+    pub candidates: Vec<String>,
+}
+
+impl ArgInfo {
+    pub fn of<T: CommandArg>(name: &'static str, help: &[&str]) -> Self {
+        Self {
+            name,
+            value_type: T::TYPE,
+            optional: T::OPTIONAL,
+            help: doc_text(help),
+            candidates: T::candidates(),
+        }
+    }
+}
+
+/// A command with typed arguments, usually declared with [`f3d_command!`](crate::f3d_command).
+pub trait TypedCommand: Sized {
+    const ACTION: &'static str;
+
+    fn description() -> String;
+    fn args() -> Vec<ArgInfo>;
+    fn from_args(args: &[String]) -> Result<Self>;
+    fn to_args(&self) -> Vec<String>;
+
+    /// E.g. `set_color <r:double> <g:double> <b:double> [alpha:double]`.
+    fn usage() -> String {
+        let mut usage = Self::ACTION.to_string();
+        for arg in Self::args() {
+            let (open, close) = if arg.optional { ('[', ']') } else { ('<', '>') };
+            usage.push_str(&format!(" {open}{}:{}{close}", arg.name, arg.value_type));
+        }
+        usage
+    }
+
+    /// Usage, description and one line per documented argument.
+    fn help() -> String {
+        let mut help = format!("Usage: {}", Self::usage());
+        let description = Self::description();
+        if !description.is_empty() {
+            help.push_str(&format!("\n{description}"));
+        }
+        let args = Self::args();
+        let width = args.iter().map(|arg| arg.name.len()).max().unwrap_or(0);
+        for arg in args.iter().filter(|arg| !arg.help.is_empty()) {
+            help.push_str(&format!("\n  {:width$}  {}", arg.name, arg.help));
+        }
+        help
+    }
+
+    /// Candidates for the argument being typed, the last of `args`, filtered by its prefix.
+    fn complete(args: &[String]) -> Vec<String> {
+        let (partial, index) = match args.split_last() {
+            Some((partial, done)) => (partial.as_str(), done.len()),
+            None => ("", 0),
+        };
+        Self::args()
+            .get(index)
+            .map(|arg| {
+                arg.candidates
+                    .iter()
+                    .filter(|candidate| candidate.starts_with(partial))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The command line triggering this command, arguments quoted when needed.
+    fn command_line(&self) -> String {
+        std::iter::once(Self::ACTION.to_string())
+            .chain(self.to_args().iter().map(|arg| quote(arg)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Parses argument `index` of a [`TypedCommand`]; used by [`f3d_command!`](crate::f3d_command).
+#[doc(hidden)]
+pub fn parse_arg<T: CommandArg>(
+    action: &str,
+    name: &str,
+    args: &[String],
+    index: usize,
+) -> Result<T> {
+    let invalid = |reason: String| Error::InvalidCommand {
+        command: action.to_string(),
+        reason,
+    };
+    match args.get(index) {
+        Some(arg) => T::parse_arg(arg)
+            .ok_or_else(|| invalid(format!("{name} expects a {}, got `{arg}`", T::TYPE))),
+        None => T::missing().ok_or_else(|| invalid(format!("missing argument {name}"))),
+    }
+}
+
+/// Rejects arguments beyond the `count` a command declares.
+#[doc(hidden)]
+pub fn check_arg_count(action: &str, args: &[String], count: usize) -> Result<()> {
+    if args.len() > count {
+        return Err(Error::InvalidCommand {
+            command: action.to_string(),
+            reason: format!("expected at most {count} arguments, got {}", args.len()),
+        });
+    }
+    Ok(())
+}
+
+/// Joins doc comment lines; used by [`f3d_command!`](crate::f3d_command).
+#[doc(hidden)]
+pub fn doc_text(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim()
+        .to_string()
+}
+
+/// Whether no required argument follows an optional one, given their `OPTIONAL` flags.
+#[doc(hidden)]
+pub const fn optional_args_are_trailing(optional: &[bool]) -> bool {
+    let mut index = 1;
+    while index < optional.len() {
+        if optional[index - 1] && !optional[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Quotes an argument the way libf3d tokenizes command lines.
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && !arg
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '#'));
+    if plain {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Declares a [`TypedCommand`] struct; doc comments become the description and argument help.
+///
+/// ```
+/// f3d::f3d_command! {
+///     /// Sets the background color.
+///     pub struct SetColor("set_color") {
+///         /// Red component, between 0 and 1.
+///         r: f64,
+///         g: f64,
+///         b: f64,
+///         alpha: Option<f64>,
+///     }
+/// }
+///
+/// use f3d::interactor::TypedCommand;
+/// assert_eq!(
+///     SetColor::usage(),
+///     "set_color <r:double> <g:double> <b:double> [alpha:double]"
+/// );
+/// let color = SetColor::from_args(&["1".into(), "0.5".into(), "0".into()]).unwrap();
+/// assert_eq!(color.command_line(), "set_color 1 0.5 0");
+/// ```
+///
+/// Optional arguments must come last, since arguments are matched by position:
+///
+/// ```compile_fail,E0080
+/// f3d::f3d_command! {
+///     struct Mid("mid") {
+///         a: Option<f64>,
+///         b: f64,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! f3d_command {
+    (
+        $(#[doc = $doc:expr])*
+        $vis:vis struct $name:ident($action:literal) {
+            $(
+                $(#[doc = $arg_doc:expr])*
+                $field:ident : $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[doc = $doc])*
+        #[derive(Debug, Clone, PartialEq)]
+        $vis struct $name {
+            $(
+                $(#[doc = $arg_doc])*
+                pub $field: $ty,
+            )*
+        }
+
+        const _: () = assert!(
+            $crate::interactor::optional_args_are_trailing(&[$(
+                <$ty as $crate::interactor::CommandArg>::OPTIONAL
+            ),*]),
+            concat!("optional arguments of `", $action, "` must come last"),
+        );
+
+        impl $crate::interactor::TypedCommand for $name {
+            const ACTION: &'static str = $action;
+
+            fn description() -> String {
+                $crate::interactor::doc_text(&[$($doc),*])
+            }
+
+            fn args() -> Vec<$crate::interactor::ArgInfo> {
+                vec![$(
+                    $crate::interactor::ArgInfo::of::<$ty>(
+                        stringify!($field),
+                        &[$($arg_doc),*],
+                    )
+                ),*]
+            }
+
+            #[allow(unused_assignments)]
+            fn from_args(args: &[String]) -> $crate::error::Result<Self> {
+                let mut index = 0;
+                $(
+                    let $field = $crate::interactor::parse_arg::<$ty>(
+                        $action,
+                        stringify!($field),
+                        args,
+                        index,
+                    )?;
+                    index += 1;
+                )*
+                $crate::interactor::check_arg_count($action, args, index)?;
+                Ok(Self { $($field),* })
+            }
+
+            fn to_args(&self) -> Vec<String> {
+                let mut args = Vec::new();
+                $(
+                    if let Some(arg) = $crate::interactor::CommandArg::format_arg(&self.$field) {
+                        args.push(arg);
+                    }
+                )*
+                args
+            }
+        }
+    };
+}
+
+impl<'a> Interactor<'a> {
+    /// Adds a command whose arguments are parsed into `T` before `callback` runs.
+    ///
+    /// Invalid arguments fail the command with its usage instead of calling `callback`.
+    pub fn add_typed_command<T, F, R>(&self, mut callback: F) -> Result<CommandHandle<'a>>
+    where
+        T: TypedCommand + 'static,
+        F: FnMut(T) -> R + 'static,
+        R: CommandOutput,
+    {
+        self.add_command(T::ACTION, move |args: &[String]| match T::from_args(args) {
+            Ok(command) => callback(command).into_outcome(),
+            Err(err) => Err(format!("{err}\nUsage: {}", T::usage())),
+        })
+    }
+
+    pub fn trigger<T: TypedCommand>(&self, command: &T) -> Result<()> {
+        self.trigger_command(command.command_line(), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::f3d_command! {
+        /// Sets the background color.
+        pub struct SetColor("set_color") {
+            /// Red component.
+            r: f64,
+            /// Green component.
+            g: f64,
+            b: f64,
+            /// Defaults to opaque.
+            alpha: Option<f64>,
+        }
+    }
+
+    crate::f3d_command! {
+        struct Screenshot("screenshot") {
+            path: PathBuf,
+            transparent: bool,
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_and_formats_args() {
+        let color = SetColor::from_args(&args(&["1", "0.5", "0"])).unwrap();
+        assert_eq!(
+            color,
+            SetColor {
+                r: 1.0,
+                g: 0.5,
+                b: 0.0,
+                alpha: None
+            }
+        );
+        assert_eq!(color.command_line(), "set_color 1 0.5 0");
+
+        let color = SetColor {
+            alpha: Some(0.25),
+            ..color
+        };
+        assert_eq!(SetColor::from_args(&color.to_args()).unwrap(), color);
+
+        let shot = Screenshot {
+            path: PathBuf::from("my shot.png"),
+            transparent: true,
+        };
+        assert_eq!(shot.command_line(), "screenshot \"my shot.png\" true");
+        assert_eq!(quote(r#"a"b\c"#), r#""a\"b\\c""#);
+    }
+
+    #[test]
+    fn validates_args() {
+        assert!(optional_args_are_trailing(&[false, true, true]));
+        assert!(!optional_args_are_trailing(&[true, false]));
+        let err = SetColor::from_args(&args(&["1", "red", "0"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid command set_color: g expects a double, got `red`"
+        );
+        let err = SetColor::from_args(&args(&["1"])).unwrap_err();
+        assert!(err.to_string().ends_with("missing argument g"));
+        let err = Screenshot::from_args(&args(&["a.png", "no", "extra"])).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("expected at most 2 arguments, got 3")
+        );
+    }
+
+    #[test]
+    fn describes_and_completes() {
+        assert_eq!(
+            SetColor::usage(),
+            "set_color <r:double> <g:double> <b:double> [alpha:double]"
+        );
+        assert_eq!(
+            SetColor::help(),
+            "Usage: set_color <r:double> <g:double> <b:double> [alpha:double]\n\
+             Sets the background color.\n  \
+             r      Red component.\n  \
+             g      Green component.\n  \
+             alpha  Defaults to opaque."
+        );
+        assert_eq!(Screenshot::description(), "");
+        assert_eq!(Screenshot::complete(&args(&["a.png", "t"])), ["true"]);
+        assert_eq!(Screenshot::complete(&args(&["a.png", ""])).len(), 2);
+        assert!(Screenshot::complete(&args(&["a"])).is_empty());
+    }
+}
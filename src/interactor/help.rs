@@ -0,0 +1,247 @@
+//! Shortcut help built from the bindings of an interactor.
+//!
+//! [`BindingHelp`] mirrors the cheatsheet libf3d draws in its own window:
+//! groups in libf3d order, and only bindings with documentation when rendered.
+
+use super::{BindingDocumentation, InteractionBind, Interactor, InteractorBindType};
+use crate::error::Result;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelpEntry {
+    pub bind: InteractionBind,
+    pub bind_type: InteractorBindType,
+    pub documentation: BindingDocumentation,
+}
+
+impl HelpEntry {
+    pub fn is_documented(&self) -> bool {
+        !self.documentation.doc.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelpGroup {
+    pub name: String,
+    pub entries: Vec<HelpEntry>,
+}
+
+impl HelpGroup {
+    pub fn documented(&self) -> impl Iterator<Item = &HelpEntry> {
+        self.entries.iter().filter(|entry| entry.is_documented())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BindingHelp {
+    pub groups: Vec<HelpGroup>,
+}
+
+impl BindingHelp {
+    /// Groups with at least one documented binding, as shown by the renderers.
+    pub fn documented_groups(&self) -> impl Iterator<Item = &HelpGroup> {
+        self.groups
+            .iter()
+            .filter(|group| group.documented().next().is_some())
+    }
+
+    pub fn group(&self, name: &str) -> Option<&HelpGroup> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        for group in self.documented_groups() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "## {}\n", markdown_escape(&group.name));
+            out.push_str("| Bind | Description | Value |\n| --- | --- | --- |\n");
+            for entry in group.documented() {
+                let _ = writeln!(
+                    out,
+                    "| `{}` | {} | {} |",
+                    entry.bind,
+                    markdown_escape(&entry.documentation.doc),
+                    markdown_escape(&entry.documentation.value)
+                );
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::from("<div class=\"f3d-bindings\">\n");
+        for group in self.documented_groups() {
+            let _ = writeln!(out, "<h2>{}</h2>", html_escape(&group.name));
+            out.push_str("<table>\n<tr><th>Bind</th><th>Description</th><th>Value</th></tr>\n");
+            for entry in group.documented() {
+                let _ = writeln!(
+                    out,
+                    "<tr><td><kbd>{}</kbd></td><td>{}</td><td>{}</td></tr>",
+                    html_escape(&entry.bind.to_string()),
+                    html_escape(&entry.documentation.doc),
+                    html_escape(&entry.documentation.value)
+                );
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("</div>\n");
+        out
+    }
+
+    /// Aligned plain-text table, one section per group.
+    pub fn to_table(&self) -> String {
+        let rows: Vec<(String, &str, &str)> = self
+            .documented_groups()
+            .flat_map(HelpGroup::documented)
+            .map(|entry| {
+                (
+                    entry.bind.to_string(),
+                    entry.documentation.doc.as_str(),
+                    entry.documentation.value.as_str(),
+                )
+            })
+            .collect();
+        let bind_width = rows
+            .iter()
+            .map(|row| row.0.chars().count())
+            .max()
+            .unwrap_or(0);
+        let doc_width = rows
+            .iter()
+            .map(|row| row.1.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        let mut rows = rows.iter();
+        for group in self.documented_groups() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "{}", group.name);
+            for (bind, doc, value) in rows.by_ref().take(group.documented().count()) {
+                let line = format!("  {bind:bind_width$}  {doc:doc_width$}  {value}");
+                let _ = writeln!(out, "{}", line.trim_end());
+            }
+        }
+        out
+    }
+}
+
+fn markdown_escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Interactor<'_> {
+    /// Every group and binding with its type, documentation and current value.
+    pub fn binding_help(&self) -> Result<BindingHelp> {
+        let mut groups = Vec::new();
+        for name in self.get_bind_groups() {
+            let mut entries = Vec::new();
+            for bind in self.get_bindings_for_group(&name)? {
+                entries.push(HelpEntry {
+                    bind_type: self.get_binding_type(bind.clone())?,
+                    documentation: self.get_binding_documentation(bind.clone())?,
+                    bind,
+                });
+            }
+            groups.push(HelpGroup { name, entries });
+        }
+        Ok(BindingHelp { groups })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(bind: &str, doc: &str, value: &str) -> HelpEntry {
+        HelpEntry {
+            bind: bind.parse().unwrap(),
+            bind_type: InteractorBindType::InteractorBindingToggle,
+            documentation: BindingDocumentation {
+                doc: doc.to_string(),
+                value: value.to_string(),
+            },
+        }
+    }
+
+    fn help() -> BindingHelp {
+        BindingHelp {
+            groups: vec![
+                HelpGroup {
+                    name: "Scene".to_string(),
+                    entries: vec![
+                        entry("G", "Toggle grid", "ON"),
+                        entry("Ctrl+Q", "", ""),
+                        entry("Shift+P", "Point size | sprites", "10"),
+                    ],
+                },
+                HelpGroup {
+                    name: "Hidden".to_string(),
+                    entries: vec![entry("H", "", "")],
+                },
+                HelpGroup {
+                    name: "Camera".to_string(),
+                    entries: vec![entry("Ctrl+Shift+R", "Reset <camera>", "")],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn keeps_documented_groups_in_order() {
+        let help = help();
+        let names: Vec<_> = help.documented_groups().map(|group| &group.name).collect();
+        assert_eq!(names, ["Scene", "Camera"]);
+        assert_eq!(help.group("Scene").unwrap().documented().count(), 2);
+        assert_eq!(help.group("Hidden").unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn renders_markdown_and_html() {
+        let help = help();
+        assert_eq!(
+            help.to_markdown(),
+            "## Scene\n\n\
+             | Bind | Description | Value |\n| --- | --- | --- |\n\
+             | `G` | Toggle grid | ON |\n\
+             | `Shift+P` | Point size \\| sprites | 10 |\n\n\
+             ## Camera\n\n\
+             | Bind | Description | Value |\n| --- | --- | --- |\n\
+             | `Ctrl+Shift+R` | Reset <camera> |  |\n"
+        );
+        let html = help.to_html();
+        assert!(html.contains("<h2>Camera</h2>"));
+        assert!(html.contains("<td><kbd>Ctrl+Shift+R</kbd></td><td>Reset &lt;camera&gt;</td>"));
+        assert!(!html.contains("Hidden"));
+    }
+
+    #[test]
+    fn renders_an_aligned_table() {
+        assert_eq!(
+            help().to_table(),
+            "Scene\n\
+             \x20 G             Toggle grid           ON\n\
+             \x20 Shift+P       Point size | sprites  10\n\n\
+             Camera\n\
+             \x20 Ctrl+Shift+R  Reset <camera>\n"
+        );
+    }
+}
@@ -0,0 +1,380 @@
+//! Keymaps: bindings loaded in bulk from TOML or JSON files.
+//!
+//! A keymap is keyed by bind string, as parsed by [`InteractionBind`]'s `FromStr`:
+//!
+//! ```toml
+//! ["Ctrl+O"]
+//! commands = ["open_file_dialog"]
+//! group = "File"
+//! doc = "Open a file"
+//!
+//! [G]
+//! commands = "toggle render.grid.enable"
+//! type = "toggle"
+//! ```
+//!
+//! `commands` is a single command or a list, `group` defaults to
+//! [`KEYMAP_BINDING_GROUP`] and `type` to `other`.
+
+use super::{InteractionBind, Interactor, InteractorBindType};
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Binding group of keymap entries that do not name one.
+pub const KEYMAP_BINDING_GROUP: &str = "Keymap";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeymapEntry {
+    #[serde(with = "commands")]
+    pub commands: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(
+        rename = "type",
+        default = "other",
+        with = "bind_type",
+        skip_serializing_if = "is_other"
+    )]
+    pub bind_type: InteractorBindType,
+    /// Shown in cheat-sheets and reported by [`Interactor::get_binding_documentation`]
+    /// once applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+}
+
+impl KeymapEntry {
+    pub fn new(commands: Vec<String>) -> Self {
+        Self {
+            commands,
+            group: None,
+            bind_type: other(),
+            doc: None,
+        }
+    }
+
+    pub fn group(&self) -> &str {
+        self.group.as_deref().unwrap_or(KEYMAP_BINDING_GROUP)
+    }
+}
+
+/// A bind used by several binding groups.
+///
+/// libf3d stores one binding per bind, so applying the later group silently overrides
+/// the earlier one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingConflict {
+    pub bind: String,
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Keymap {
+    pub bindings: BTreeMap<String, KeymapEntry>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind(&mut self, bind: &str, entry: KeymapEntry) -> &mut Self {
+        self.bindings.insert(bind.to_string(), entry);
+        self
+    }
+
+    /// Loads a `.toml` or `.json` keymap.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let format = extension(path)?;
+        let content = std::fs::read_to_string(path)?;
+        match format.as_str() {
+            "toml" => Self::from_toml(&content),
+            _ => Self::from_json(&content),
+        }
+    }
+
+    /// Saves as TOML or JSON depending on the extension of `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = match extension(path)?.as_str() {
+            "toml" => self.to_toml()?,
+            _ => self.to_json()?,
+        };
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content).map_err(|err| Error::InvalidConfig(err.to_string()))
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).map_err(|err| Error::InvalidConfig(err.to_string()))
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        serde_json::from_str(content).map_err(|err| Error::InvalidConfig(err.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|err| Error::InvalidConfig(err.to_string()))
+    }
+
+    /// Adds every binding to `interactor`, replacing existing bindings of the same binds.
+    pub fn apply(&self, interactor: &Interactor) -> Result<()> {
+        for (bind, entry) in &self.bindings {
+            let bind: InteractionBind = bind.parse()?;
+            interactor.remove_binding(bind.clone())?;
+            interactor.add_binding(
+                bind.clone(),
+                entry.commands.clone(),
+                Some(entry.group()),
+                entry.bind_type,
+                0,
+            )?;
+            if let Some(doc) = &entry.doc {
+                interactor.set_binding_documentation(bind, doc)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Binds used by several groups, within the keymap or with the bindings of `interactor`.
+    ///
+    /// Binds are compared once parsed, so `None+O` and `O` collide.
+    pub fn conflicts(&self, interactor: &Interactor) -> Result<Vec<BindingConflict>> {
+        let mut binds = Vec::new();
+        for (group, bind) in interactor.all_bindings()? {
+            binds.push((group, bind.to_string()));
+        }
+        for (bind, entry) in &self.bindings {
+            let bind = bind.parse::<InteractionBind>()?.to_string();
+            binds.push((entry.group().to_string(), bind));
+        }
+        Ok(find_conflicts(binds))
+    }
+
+    /// Plain-text listing grouped by binding group, documentation falling back to the commands.
+    pub fn cheat_sheet(&self) -> String {
+        let mut groups: BTreeMap<&str, Vec<(&str, &KeymapEntry)>> = BTreeMap::new();
+        for (bind, entry) in &self.bindings {
+            groups.entry(entry.group()).or_default().push((bind, entry));
+        }
+        let width = self
+            .bindings
+            .keys()
+            .map(|bind| bind.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut sheet = String::new();
+        for (group, entries) in groups {
+            if !sheet.is_empty() {
+                sheet.push('\n');
+            }
+            sheet.push_str(group);
+            sheet.push('\n');
+            for (bind, entry) in entries {
+                let description = match &entry.doc {
+                    Some(doc) => doc.clone(),
+                    None => entry.commands.join("; "),
+                };
+                sheet.push_str(&format!("  {bind:width$}  {description}\n"));
+            }
+        }
+        sheet
+    }
+}
+
+/// Groups `(group, bind)` pairs by bind, keeping binds found in more than one group.
+fn find_conflicts(binds: impl IntoIterator<Item = (String, String)>) -> Vec<BindingConflict> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (group, bind) in binds {
+        let bind_groups = groups.entry(bind).or_default();
+        if !bind_groups.contains(&group) {
+            bind_groups.push(group);
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, groups)| groups.len() > 1)
+        .map(|(bind, groups)| BindingConflict { bind, groups })
+        .collect()
+}
+
+fn extension(path: &Path) -> Result<String> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("toml" | "json") => Ok(extension.unwrap()),
+        _ => Err(Error::InvalidConfig(format!(
+            "{}: keymaps must be .toml or .json files",
+            path.display()
+        ))),
+    }
+}
+
+fn other() -> InteractorBindType {
+    InteractorBindType::InteractorBindingOther
+}
+
+fn is_other(bind_type: &InteractorBindType) -> bool {
+    *bind_type == other()
+}
+
+mod bind_type {
+    use super::InteractorBindType;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        bind_type: &InteractorBindType,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(bind_type.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<InteractorBindType, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A single command is written as a string, several as a list.
+mod commands {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    pub fn serialize<S: Serializer>(commands: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+        match commands {
+            [command] => command.serialize(serializer),
+            _ => commands.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<String>, D::Error> {
+        Ok(match OneOrMany::deserialize(deserializer)? {
+            OneOrMany::One(command) => vec![command],
+            OneOrMany::Many(commands) => commands,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+["Ctrl+O"]
+commands = ["open_file_dialog"]
+group = "File"
+doc = "Open a file"
+
+[G]
+commands = "toggle render.grid.enable"
+type = "toggle"
+
+["Shift+R"]
+commands = ["reset_camera", "render"]
+"#;
+
+    #[test]
+    fn round_trips_toml_and_json() {
+        let keymap = Keymap::from_toml(TOML).unwrap();
+        assert_eq!(keymap.bindings.len(), 3);
+        let grid = &keymap.bindings["G"];
+        assert_eq!(grid.commands, ["toggle render.grid.enable"]);
+        assert_eq!(grid.bind_type, InteractorBindType::InteractorBindingToggle);
+        assert_eq!(grid.group(), KEYMAP_BINDING_GROUP);
+        assert_eq!(keymap.bindings["Shift+R"].commands.len(), 2);
+
+        assert_eq!(
+            Keymap::from_toml(&keymap.to_toml().unwrap()).unwrap(),
+            keymap
+        );
+        let json = keymap.to_json().unwrap();
+        assert!(json.contains(r#""type": "toggle""#));
+        assert_eq!(Keymap::from_json(&json).unwrap(), keymap);
+    }
+
+    #[test]
+    fn rejects_invalid_keymaps() {
+        assert!(Keymap::from_toml("[G]\ncommands = 1\n").is_err());
+        let err = Keymap::from_json(r#"{"G": {"commands": "x", "type": "sticky"}}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown binding type `sticky`"));
+        let err = Keymap::load("keymap.yaml").unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("keymaps must be .toml or .json files")
+        );
+        let missing = std::env::temp_dir().join("f3d-rs-missing-keymap.toml");
+        assert!(matches!(Keymap::load(missing), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn applies_bindings_with_documentation() {
+        use crate::engine::{Engine, EngineBackend, EngineContext};
+
+        let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+        let interactor = engine.interactor();
+        Keymap::from_toml(TOML).unwrap().apply(&interactor).unwrap();
+        let open: InteractionBind = "Ctrl+O".parse().unwrap();
+        assert_eq!(
+            interactor
+                .get_binding_documentation(open.clone())
+                .unwrap()
+                .doc,
+            "Open a file"
+        );
+        interactor.remove_binding(open.clone()).unwrap();
+        assert!(
+            interactor
+                .get_binding_documentation(open)
+                .unwrap()
+                .doc
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn finds_conflicts_between_groups() {
+        let binds = [
+            ("Camera", "Ctrl+O"),
+            ("File", "Ctrl+O"),
+            ("File", "Ctrl+O"),
+            ("Scene", "G"),
+            ("Keymap", "Shift+R"),
+        ]
+        .map(|(group, bind)| (group.to_string(), bind.to_string()));
+        assert_eq!(
+            find_conflicts(binds),
+            [BindingConflict {
+                bind: "Ctrl+O".to_string(),
+                groups: vec!["Camera".to_string(), "File".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn writes_a_cheat_sheet() {
+        let keymap = Keymap::from_toml(TOML).unwrap();
+        assert_eq!(
+            keymap.cheat_sheet(),
+            "File\n  Ctrl+O   Open a file\n\n\
+             Keymap\n  G        toggle render.grid.enable\n  Shift+R  reset_camera; render\n"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Pure-Rust mesh readers and writers.
+//!
+//! The readers stream their input and produce triangulated [`F3DMesh`]es that
+//! can be passed to [`Scene::add_mesh`](crate::scene::Scene::add_mesh) without
+//! going through the VTK based libf3d readers.
+
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use crate::error::{Error, Result};
+use crate::types::F3DMesh;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+    Obj,
+    Stl,
+    Ply,
+}
+
+impl MeshFormat {
+    /// Case-insensitive lookup, with or without the leading dot.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension
+            .trim_start_matches('.')
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "obj" => Some(MeshFormat::Obj),
+            "stl" => Some(MeshFormat::Stl),
+            "ply" => Some(MeshFormat::Ply),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| Self::from_extension(&extension.to_string_lossy()))
+    }
+}
+
+pub fn read_mesh(reader: impl Read, format: MeshFormat) -> Result<F3DMesh> {
+    match format {
+        MeshFormat::Obj => obj::read(BufReader::new(reader)),
+        MeshFormat::Stl => stl::read(reader),
+        MeshFormat::Ply => ply::read(BufReader::new(reader)),
+    }
+}
+
+/// Reads a mesh file, picking the format from its extension.
+pub fn load_mesh(path: impl AsRef<Path>) -> Result<F3DMesh> {
+    let path = path.as_ref();
+    let format = MeshFormat::from_path(path)
+        .ok_or_else(|| Error::UnsupportedFile(path.display().to_string()))?;
+    read_mesh(File::open(path)?, format)
+}
+
+/// Writes OBJ, binary STL or binary little-endian PLY.
+///
+/// Every writer validates the mesh first and fails with [`Error::InvalidMesh`].
+pub fn write_mesh(writer: impl Write, mesh: &F3DMesh, format: MeshFormat) -> Result<()> {
+    match format {
+        MeshFormat::Obj => obj::write(writer, mesh),
+        MeshFormat::Stl => stl::write_binary(writer, mesh),
+        MeshFormat::Ply => ply::write(writer, mesh, ply::PlyFormat::BinaryLittleEndian),
+    }
+}
+
+/// Writes a mesh file, picking the format from its extension.
+pub fn save_mesh(path: impl AsRef<Path>, mesh: &F3DMesh) -> Result<()> {
+    let path = path.as_ref();
+    let format = MeshFormat::from_path(path)
+        .ok_or_else(|| Error::UnsupportedFile(path.display().to_string()))?;
+    // Before creating the file, so an invalid mesh does not leave an empty one behind.
+    mesh.validate()?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_mesh(&mut writer, mesh, format)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Splits every face with more than three sides into a triangle fan.
+pub fn triangulate(mesh: &F3DMesh) -> Result<F3DMesh> {
+    mesh.validate()?;
+    let mut face_sides = Vec::with_capacity(mesh.face_count());
+    let mut face_indices = Vec::with_capacity(mesh.face_indices().len());
+    for face in faces(mesh) {
+        push_triangulated(&mut face_sides, &mut face_indices, face);
+    }
+    Ok(F3DMesh::new(
+        mesh.points().to_vec(),
+        mesh.normals().to_vec(),
+        mesh.texcoords().to_vec(),
+        face_sides,
+        face_indices,
+    ))
+}
+
+/// Appends `face` as a triangle fan; faces with fewer than three sides are kept as is.
+pub(crate) fn push_triangulated(
+    face_sides: &mut Vec<u32>,
+    face_indices: &mut Vec<u32>,
+    face: &[u32],
+) {
+    if face.len() <= 3 {
+        face_sides.push(face.len() as u32);
+        face_indices.extend_from_slice(face);
+        return;
+    }
+    for i in 1..face.len() - 1 {
+        face_sides.push(3);
+        face_indices.extend_from_slice(&[face[0], face[i], face[i + 1]]);
+    }
+}
+
+/// Iterates over the faces of a mesh as index slices; the mesh must be valid.
+pub(crate) fn faces(mesh: &F3DMesh) -> impl Iterator<Item = &[u32]> {
+    let mut offset = 0;
+    mesh.face_sides().iter().map(move |&sides| {
+        let face = &mesh.face_indices()[offset..offset + sides as usize];
+        offset += sides as usize;
+        face
+    })
+}
+
+pub(crate) fn point(mesh: &F3DMesh, index: u32) -> [f32; 3] {
+    let i = index as usize * 3;
+    [mesh.points()[i], mesh.points()[i + 1], mesh.points()[i + 2]]
+}
+
+/// Unit normal of the triangle `a, b, c`, or zero when it is degenerate.
+pub(crate) fn triangle_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let cross = [
+        ab[1] * ac[2] - ab[2] * ac[1],
+        ab[2] * ac[0] - ab[0] * ac[2],
+        ab[0] * ac[1] - ab[1] * ac[0],
+    ];
+    let length = (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
+    if length == 0.0 {
+        return [0.0; 3];
+    }
+    cross.map(|v| v / length)
+}
+
+pub(crate) fn parse_error(format: &'static str, reason: impl Into<String>) -> Error {
+    Error::MeshParse {
+        format,
+        reason: reason.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(MeshFormat::from_extension("OBJ"), Some(MeshFormat::Obj));
+        assert_eq!(MeshFormat::from_extension(".stl"), Some(MeshFormat::Stl));
+        assert_eq!(MeshFormat::from_path("a/b/mesh.Ply"), Some(MeshFormat::Ply));
+        assert_eq!(MeshFormat::from_path("mesh.gltf"), None);
+    }
+
+    #[test]
+    fn triangulates_fans() {
+        let mesh = triangulate(&primitives::cube(1.0)).unwrap();
+        assert_eq!(mesh.face_count(), 12);
+        assert!(mesh.face_sides().iter().all(|&sides| sides == 3));
+        assert_eq!(&mesh.face_indices()[..6], &[0, 1, 2, 0, 2, 3]);
+        mesh.validate().unwrap();
+    }
+
+    #[test]
+    fn save_and_load_every_format() {
+        let dir = std::env::temp_dir().join(format!("f3d-io-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mesh = triangulate(&primitives::uv_sphere(1.0, 8, 4)).unwrap();
+
+        for extension in ["obj", "ply"] {
+            let path = dir.join(format!("sphere.{extension}"));
+            save_mesh(&path, &mesh).unwrap();
+            assert_eq!(load_mesh(&path).unwrap(), mesh);
+        }
+
+        let path = dir.join("sphere.stl");
+        save_mesh(&path, &mesh).unwrap();
+        let loaded = load_mesh(&path).unwrap();
+        assert_eq!(loaded.face_count(), mesh.face_count());
+        assert_eq!(loaded.point_count(), mesh.face_count() * 3);
+
+        assert!(matches!(
+            load_mesh(dir.join("sphere.gltf")),
+            Err(Error::UnsupportedFile(_))
+        ));
+        assert!(matches!(
+            load_mesh(dir.join("missing.obj")),
+            Err(Error::Io(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_meshes() {
+        let short_faces = F3DMesh::new(vec![0.0; 9], vec![], vec![], vec![4], vec![0, 1, 2]);
+        let out_of_range = F3DMesh::new(vec![0.0; 9], vec![], vec![], vec![3], vec![0, 1, 3]);
+        for mesh in [short_faces, out_of_range] {
+            assert!(matches!(triangulate(&mesh), Err(Error::InvalidMesh(_))));
+            for format in [MeshFormat::Obj, MeshFormat::Stl, MeshFormat::Ply] {
+                assert!(matches!(
+                    write_mesh(Vec::new(), &mesh, format),
+                    Err(Error::InvalidMesh(_))
+                ));
+            }
+            assert!(matches!(
+                stl::write_ascii(Vec::new(), &mesh, "mesh"),
+                Err(Error::InvalidMesh(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Wavefront OBJ geometry (`v`, `vt`, `vn` and `f` statements).
+//!
+//! Points keep the order of the `v` statements. A position referenced with
+//! several texture coordinate or normal combinations is duplicated at the end.
+
+use super::{faces, parse_error, push_triangulated};
+use crate::error::Result;
+use crate::types::F3DMesh;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+const FORMAT: &str = "OBJ";
+
+type Corner = (usize, Option<usize>, Option<usize>);
+
+pub fn read(reader: impl BufRead) -> Result<F3DMesh> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut texcoords: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+
+    // Corner used by each point, `None` while a position is unreferenced.
+    let mut slots: Vec<Option<Corner>> = Vec::new();
+    let mut duplicates: HashMap<Corner, u32> = HashMap::new();
+    let mut all_texcoords = true;
+    let mut all_normals = true;
+    let mut face_sides = Vec::new();
+    let mut face_indices = Vec::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or_default();
+        let error = |reason: String| parse_error(FORMAT, format!("line {}: {reason}", number + 1));
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                positions.push(floats::<3>(&mut tokens, 3).map_err(error)?);
+                slots.push(None);
+            }
+            Some("vt") => {
+                let [u, v] = floats::<2>(&mut tokens, 1).map_err(error)?;
+                texcoords.push([u, v]);
+            }
+            Some("vn") => normals.push(floats::<3>(&mut tokens, 3).map_err(error)?),
+            Some("f") => {
+                let mut face = Vec::new();
+                for token in tokens {
+                    let corner = corner(token, positions.len(), texcoords.len(), normals.len())
+                        .map_err(error)?;
+                    all_texcoords &= corner.1.is_some();
+                    all_normals &= corner.2.is_some();
+                    let index = match slots[corner.0] {
+                        None => {
+                            slots[corner.0] = Some(corner);
+                            corner.0 as u32
+                        }
+                        Some(existing) if existing == corner => corner.0 as u32,
+                        Some(_) => *duplicates.entry(corner).or_insert_with(|| {
+                            slots.push(Some(corner));
+                            (slots.len() - 1) as u32
+                        }),
+                    };
+                    face.push(index);
+                }
+                if face.len() < 3 {
+                    return Err(error(format!("face has {} vertices", face.len())));
+                }
+                push_triangulated(&mut face_sides, &mut face_indices, &face);
+            }
+            _ => {}
+        }
+    }
+
+    if face_sides.is_empty() {
+        // Point clouds only carry attributes when the streams line up.
+        all_texcoords = texcoords.len() == positions.len();
+        all_normals = normals.len() == positions.len();
+    }
+
+    let mut points = Vec::with_capacity(slots.len());
+    let mut point_texcoords = Vec::new();
+    let mut point_normals = Vec::new();
+    for (slot, corner) in slots.iter().enumerate() {
+        // Unreferenced positions fall back to the attribute at the same index.
+        let (position, texcoord, normal) = corner.unwrap_or((slot, Some(slot), Some(slot)));
+        points.push(positions[position]);
+        if all_texcoords {
+            point_texcoords.push(
+                texcoord
+                    .and_then(|i| texcoords.get(i))
+                    .copied()
+                    .unwrap_or_default(),
+            );
+        }
+        if all_normals {
+            point_normals.push(
+                normal
+                    .and_then(|i| normals.get(i))
+                    .copied()
+                    .unwrap_or_default(),
+            );
+        }
+    }
+    F3DMesh::builder()
+        .points(&points)
+        .texcoords(&point_texcoords)
+        .normals(&point_normals)
+        .faces(&face_sides, &face_indices)
+        .build()
+}
+
+/// Parses at least `required` and at most `N` floats, defaulting the rest to zero.
+fn floats<'t, const N: usize>(
+    tokens: &mut impl Iterator<Item = &'t str>,
+    required: usize,
+) -> std::result::Result<[f32; N], String> {
+    let mut values = [0.0; N];
+    let mut count = 0;
+    for (value, token) in values.iter_mut().zip(tokens) {
+        *value = token
+            .parse()
+            .map_err(|_| format!("`{token}` is not a number"))?;
+        count += 1;
+    }
+    if count < required {
+        return Err(format!("expected {required} values, got {count}"));
+    }
+    Ok(values)
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into zero-based indices.
+fn corner(
+    token: &str,
+    positions: usize,
+    texcoords: usize,
+    normals: usize,
+) -> std::result::Result<Corner, String> {
+    let mut parts = token.split('/');
+    let position = resolve(parts.next().unwrap_or_default(), positions, "vertex")?;
+    let texcoord = match parts.next() {
+        Some("") | None => None,
+        Some(index) => Some(resolve(index, texcoords, "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(index) => Some(resolve(index, normals, "normal")?),
+    };
+    Ok((position, texcoord, normal))
+}
+
+/// Resolves a one-based or negative (relative) OBJ index.
+fn resolve(token: &str, count: usize, what: &str) -> std::result::Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("`{token}` is not a {what} index"))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{what} index {index} is out of range"));
+    }
+    Ok(resolved as usize)
+}
+
+/// Writes every point with its attributes, faces referencing the same index for each stream.
+pub fn write(mut writer: impl Write, mesh: &F3DMesh) -> Result<()> {
+    mesh.validate()?;
+    for point in mesh.points().chunks(3) {
+        writeln!(writer, "v {} {} {}", point[0], point[1], point[2])?;
+    }
+    for texcoord in mesh.texcoords().chunks(2) {
+        writeln!(writer, "vt {} {}", texcoord[0], texcoord[1])?;
+    }
+    for normal in mesh.normals().chunks(3) {
+        writeln!(writer, "vn {} {} {}", normal[0], normal[1], normal[2])?;
+    }
+
+    let has_texcoords = !mesh.texcoords().is_empty();
+    let has_normals = !mesh.normals().is_empty();
+    for face in faces(mesh) {
+        write!(writer, "f")?;
+        for &index in face {
+            let index = index + 1;
+            match (has_texcoords, has_normals) {
+                (false, false) => write!(writer, " {index}")?,
+                (true, false) => write!(writer, " {index}/{index}")?,
+                (false, true) => write!(writer, " {index}//{index}")?,
+                (true, true) => write!(writer, " {index}/{index}/{index}")?,
+            }
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::io::triangulate;
+    use crate::primitives;
+
+    fn round_trip(mesh: &F3DMesh) -> F3DMesh {
+        let mut bytes = Vec::new();
+        write(&mut bytes, mesh).unwrap();
+        read(bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_triangulated_meshes() {
+        for mesh in [
+            primitives::cube(2.0),
+            primitives::torus(1.0, 0.25, 12, 6),
+            primitives::icosphere(1.0, 1),
+        ] {
+            let mesh = triangulate(&mesh).unwrap();
+            assert_eq!(round_trip(&mesh), mesh);
+        }
+    }
+
+    #[test]
+    fn triangulates_polygons() {
+        let source = "\
+# quad and pentagon
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0.5 1.5 0
+f 1 2 3 4
+f 4 3 5
+f -5 -4 -3 -2 -1
+";
+        let mesh = read(source.as_bytes()).unwrap();
+        assert_eq!(mesh.point_count(), 5);
+        assert_eq!(mesh.face_sides(), &[3, 3, 3, 3, 3, 3]);
+        assert_eq!(&mesh.face_indices()[..9], &[0, 1, 2, 0, 2, 3, 3, 2, 4]);
+        assert!(mesh.normals().is_empty());
+        assert!(mesh.texcoords().is_empty());
+    }
+
+    #[test]
+    fn splits_points_with_several_normals() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 0 1
+vn 0 0 -1
+vn 0 -1 0
+f 1//1 3//1 2//1
+f 1//2 2//2 4//2
+";
+        let mesh = read(source.as_bytes()).unwrap();
+        assert_eq!(mesh.point_count(), 6);
+        assert_eq!(mesh.face_indices(), &[0, 2, 1, 4, 5, 3]);
+        assert_eq!(&mesh.points()[12..], &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(&mesh.normals()[12..15], &[0.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let err = read("v 0 0 0\nv 1 0 0\nf 1 2 3\n".as_bytes()).unwrap_err();
+        assert!(
+            matches!(&err, Error::MeshParse { format: "OBJ", reason } if reason.starts_with("line 3"))
+        );
+
+        let err = read("v 0 0\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::MeshParse { .. }));
+    }
+}
@@ -0,0 +1,519 @@
+//! Stanford PLY in ASCII and binary encodings.
+//!
+//! Only the `vertex` (`x y z`, `nx ny nz`, `u v`/`s t`) and `face`
+//! (`vertex_indices`) elements are used; other elements and properties are
+//! skipped.
+
+use super::{faces, parse_error, push_triangulated};
+use crate::error::{Error, Result};
+use crate::types::F3DMesh;
+use std::io::{BufRead, ErrorKind, Write};
+
+const FORMAT: &str = "PLY";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+impl PlyFormat {
+    fn name(self) -> &'static str {
+        match self {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        macro_rules! decode {
+            ($ty:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                let value = if big_endian {
+                    <$ty>::from_be_bytes(bytes)
+                } else {
+                    <$ty>::from_le_bytes(bytes)
+                };
+                value as f64
+            }};
+        }
+        match self {
+            Scalar::I8 => decode!(i8),
+            Scalar::U8 => decode!(u8),
+            Scalar::I16 => decode!(i16),
+            Scalar::U16 => decode!(u16),
+            Scalar::I32 => decode!(i32),
+            Scalar::U32 => decode!(u32),
+            Scalar::F32 => decode!(f32),
+            Scalar::F64 => decode!(f64),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar {
+        name: String,
+        value: Scalar,
+    },
+    List {
+        name: String,
+        count: Scalar,
+        item: Scalar,
+    },
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: PlyFormat,
+    elements: Vec<Element>,
+}
+
+/// Reads the header line by line so that a binary body is left untouched in `reader`.
+fn read_header(reader: &mut impl BufRead, line: &mut usize) -> Result<Header> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        text.clear();
+        if reader.read_line(&mut text)? == 0 {
+            return Err(parse_error(FORMAT, "missing end_header"));
+        }
+        *line += 1;
+        let error = |reason: &str| parse_error(FORMAT, format!("line {line}: {reason}"));
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+
+        if *line == 1 {
+            if tokens != ["ply"] {
+                return Err(error("missing `ply` magic"));
+            }
+            continue;
+        }
+        match tokens.as_slice() {
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(error("unknown format")),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| error("invalid element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let property = Property::List {
+                    name: name.to_string(),
+                    count: Scalar::parse(count).ok_or_else(|| error("unknown list count type"))?,
+                    item: Scalar::parse(item).ok_or_else(|| error("unknown list item type"))?,
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before element"))?
+                    .properties
+                    .push(property);
+            }
+            ["property", value, name] => {
+                let property = Property::Scalar {
+                    name: name.to_string(),
+                    value: Scalar::parse(value).ok_or_else(|| error("unknown property type"))?,
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| error("property before element"))?
+                    .properties
+                    .push(property);
+            }
+            ["end_header"] => break,
+            ["comment" | "obj_info", ..] | [] => {}
+            _ => return Err(error("unexpected header line")),
+        }
+    }
+
+    Ok(Header {
+        format: format.ok_or_else(|| parse_error(FORMAT, "missing format line"))?,
+        elements,
+    })
+}
+
+/// Reads property values from the body, one token or binary scalar at a time.
+struct Body<R> {
+    reader: R,
+    format: PlyFormat,
+    tokens: std::vec::IntoIter<String>,
+    line: usize,
+}
+
+impl<R: BufRead> Body<R> {
+    fn error(&self, reason: &str) -> Error {
+        match self.format {
+            PlyFormat::Ascii => parse_error(FORMAT, format!("line {}: {reason}", self.line)),
+            _ => parse_error(FORMAT, reason),
+        }
+    }
+
+    fn value(&mut self, scalar: Scalar) -> Result<f64> {
+        if self.format != PlyFormat::Ascii {
+            let mut buffer = [0; 8];
+            let bytes = &mut buffer[..scalar.size()];
+            self.reader
+                .read_exact(bytes)
+                .map_err(|err| match err.kind() {
+                    ErrorKind::UnexpectedEof => self.error("unexpected end of data"),
+                    _ => Error::Io(err),
+                })?;
+            return Ok(scalar.decode(bytes, self.format == PlyFormat::BinaryBigEndian));
+        }
+
+        loop {
+            if let Some(token) = self.tokens.next() {
+                // Parsing floats at their own precision keeps them exact.
+                let value = match scalar {
+                    Scalar::F32 => token.parse::<f32>().map(f64::from),
+                    _ => token.parse::<f64>(),
+                };
+                return value.map_err(|_| self.error(&format!("`{token}` is not a number")));
+            }
+            let mut text = String::new();
+            if self.reader.read_line(&mut text)? == 0 {
+                return Err(self.error("unexpected end of data"));
+            }
+            self.line += 1;
+            self.tokens = text
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+
+    fn index(&mut self, scalar: Scalar) -> Result<u32> {
+        let value = self.value(scalar)?;
+        if value < 0.0 || value.fract() != 0.0 || value > u32::MAX as f64 {
+            return Err(self.error(&format!("{value} is not a valid index")));
+        }
+        Ok(value as u32)
+    }
+}
+
+/// Property slots of the vertex element that map to mesh attributes.
+const POSITION: [&str; 3] = ["x", "y", "z"];
+const NORMAL: [&str; 3] = ["nx", "ny", "nz"];
+const TEXCOORDS: [[&str; 2]; 4] = [
+    ["u", "v"],
+    ["s", "t"],
+    ["texture_u", "texture_v"],
+    ["texture_s", "texture_t"],
+];
+
+pub fn read(mut reader: impl BufRead) -> Result<F3DMesh> {
+    let mut line = 0;
+    let header = read_header(&mut reader, &mut line)?;
+    let mut body = Body {
+        reader,
+        format: header.format,
+        tokens: Vec::new().into_iter(),
+        line,
+    };
+
+    let mut points: Vec<f32> = Vec::new();
+    let mut normals: Vec<f32> = Vec::new();
+    let mut texcoords: Vec<f32> = Vec::new();
+    let mut face_sides = Vec::new();
+    let mut face_indices = Vec::new();
+
+    for element in &header.elements {
+        let names: Vec<&str> = element
+            .properties
+            .iter()
+            .map(|property| match property {
+                Property::Scalar { name, .. } | Property::List { name, .. } => name.as_str(),
+            })
+            .collect();
+        let find = |wanted: &[&str]| -> Option<Vec<usize>> {
+            wanted
+                .iter()
+                .map(|name| names.iter().position(|n| n == name))
+                .collect()
+        };
+
+        let is_vertex = element.name == "vertex";
+        let position = find(&POSITION).filter(|_| is_vertex);
+        let normal = find(&NORMAL).filter(|_| is_vertex);
+        let texcoord = TEXCOORDS
+            .iter()
+            .find_map(|pair| find(pair))
+            .filter(|_| is_vertex);
+        let face_list = names
+            .iter()
+            .position(|&name| name == "vertex_indices" || name == "vertex_index")
+            .filter(|_| element.name == "face");
+        if is_vertex && position.is_none() {
+            return Err(parse_error(FORMAT, "vertex element without x, y and z"));
+        }
+
+        let mut values = vec![0.0; element.properties.len()];
+        for _ in 0..element.count {
+            for (slot, property) in element.properties.iter().enumerate() {
+                match property {
+                    Property::Scalar { value, .. } => values[slot] = body.value(*value)?,
+                    Property::List { count, item, .. } => {
+                        let count = body.index(*count)? as usize;
+                        // The count comes from the file: grow as values are read instead of
+                        // reserving it up front.
+                        let mut list = Vec::new();
+                        for _ in 0..count {
+                            list.push(body.index(*item)?);
+                        }
+                        if face_list == Some(slot) {
+                            push_triangulated(&mut face_sides, &mut face_indices, &list);
+                        }
+                    }
+                }
+            }
+            let gather = |slots: &Option<Vec<usize>>, out: &mut Vec<f32>| {
+                if let Some(slots) = slots {
+                    out.extend(slots.iter().map(|&slot| values[slot] as f32));
+                }
+            };
+            gather(&position, &mut points);
+            gather(&normal, &mut normals);
+            gather(&texcoord, &mut texcoords);
+        }
+    }
+
+    F3DMesh::builder()
+        .points_flat(&points)
+        .normals_flat(&normals)
+        .texcoords_flat(&texcoords)
+        .faces(&face_sides, &face_indices)
+        .build()
+}
+
+pub fn write(mut writer: impl Write, mesh: &F3DMesh, format: PlyFormat) -> Result<()> {
+    mesh.validate()?;
+    let has_normals = !mesh.normals().is_empty();
+    let has_texcoords = !mesh.texcoords().is_empty();
+    let wide_faces = mesh
+        .face_sides()
+        .iter()
+        .any(|&sides| sides > u8::MAX as u32);
+
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {} 1.0", format.name())?;
+    writeln!(writer, "element vertex {}", mesh.point_count())?;
+    for name in POSITION {
+        writeln!(writer, "property float {name}")?;
+    }
+    if has_normals {
+        for name in NORMAL {
+            writeln!(writer, "property float {name}")?;
+        }
+    }
+    if has_texcoords {
+        writeln!(writer, "property float u")?;
+        writeln!(writer, "property float v")?;
+    }
+    writeln!(writer, "element face {}", mesh.face_count())?;
+    let count_type = if wide_faces { "uint" } else { "uchar" };
+    writeln!(writer, "property list {count_type} uint vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    for point in 0..mesh.point_count() {
+        let mut values = mesh.points()[point * 3..point * 3 + 3].to_vec();
+        if has_normals {
+            values.extend_from_slice(&mesh.normals()[point * 3..point * 3 + 3]);
+        }
+        if has_texcoords {
+            values.extend_from_slice(&mesh.texcoords()[point * 2..point * 2 + 2]);
+        }
+        match format {
+            PlyFormat::Ascii => {
+                let line: Vec<String> = values.iter().map(f32::to_string).collect();
+                writeln!(writer, "{}", line.join(" "))?;
+            }
+            PlyFormat::BinaryLittleEndian => {
+                for value in values {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+            }
+            PlyFormat::BinaryBigEndian => {
+                for value in values {
+                    writer.write_all(&value.to_be_bytes())?;
+                }
+            }
+        }
+    }
+
+    for face in faces(mesh) {
+        let sides = face.len() as u32;
+        match format {
+            PlyFormat::Ascii => {
+                let line: Vec<String> = face.iter().map(u32::to_string).collect();
+                writeln!(writer, "{sides} {}", line.join(" "))?;
+            }
+            PlyFormat::BinaryLittleEndian => {
+                if wide_faces {
+                    writer.write_all(&sides.to_le_bytes())?;
+                } else {
+                    writer.write_all(&[sides as u8])?;
+                }
+                for index in face {
+                    writer.write_all(&index.to_le_bytes())?;
+                }
+            }
+            PlyFormat::BinaryBigEndian => {
+                if wide_faces {
+                    writer.write_all(&sides.to_be_bytes())?;
+                } else {
+                    writer.write_all(&[sides as u8])?;
+                }
+                for index in face {
+                    writer.write_all(&index.to_be_bytes())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::triangulate;
+    use crate::primitives;
+
+    #[test]
+    fn round_trips_every_encoding() {
+        let mesh = triangulate(&primitives::torus(1.0, 0.3, 10, 5)).unwrap();
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let mut bytes = Vec::new();
+            write(&mut bytes, &mesh, format).unwrap();
+            assert_eq!(read(bytes.as_slice()).unwrap(), mesh, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn triangulates_and_skips_unknown_data() {
+        let source = "\
+ply
+format ascii 1.0
+comment made by hand
+element vertex 5
+property double x
+property double y
+property double z
+property uchar red
+element edge 1
+property int vertex1
+property int vertex2
+element face 2
+property uchar flags
+property list uchar int vertex_index
+end_header
+0 0 0 255
+1 0 0 255
+1 1 0 255
+0 1 0 255
+0.5 1.5 0 255
+0 1
+7 4 0 1 2 3
+0 3 3 2 4
+";
+        let mesh = read(source.as_bytes()).unwrap();
+        assert_eq!(mesh.point_count(), 5);
+        assert_eq!(mesh.face_indices(), &[0, 1, 2, 0, 2, 3, 3, 2, 4]);
+        assert!(mesh.normals().is_empty());
+        assert!(mesh.texcoords().is_empty());
+    }
+
+    #[test]
+    fn reports_truncated_and_invalid_data() {
+        let mut bytes = Vec::new();
+        write(
+            &mut bytes,
+            &primitives::cube(1.0),
+            PlyFormat::BinaryLittleEndian,
+        )
+        .unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(matches!(
+            read(bytes.as_slice()),
+            Err(Error::MeshParse { format: "PLY", reason }) if reason == "unexpected end of data"
+        ));
+
+        let source = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nend_header\n0 zero 0\n";
+        assert!(matches!(
+            read(source.as_bytes()),
+            Err(Error::MeshParse { reason, .. }) if reason == "line 8: `zero` is not a number"
+        ));
+
+        let mut huge_list = b"ply\nformat binary_little_endian 1.0\nelement vertex 0\n\
+property float x\nproperty float y\nproperty float z\nelement face 1\n\
+property list uint int vertex_indices\nend_header\n"
+            .to_vec();
+        huge_list.extend(u32::MAX.to_le_bytes());
+        huge_list.extend(0i32.to_le_bytes());
+        assert!(matches!(
+            read(huge_list.as_slice()),
+            Err(Error::MeshParse { reason, .. }) if reason == "unexpected end of data"
+        ));
+
+        assert!(matches!(
+            read("obj\n".as_bytes()),
+            Err(Error::MeshParse { reason, .. }) if reason.starts_with("line 1")
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+//! ASCII and binary STL.
+//!
+//! STL has no shared vertices, so every facet gets its own three points
+//! carrying the facet normal.
+
+use super::{faces, parse_error, point, triangle_normal};
+use crate::error::{Error, Result};
+use crate::types::F3DMesh;
+use std::io::{BufRead, BufReader, Cursor, ErrorKind, Read, Write};
+
+const FORMAT: &str = "STL";
+const HEADER_SIZE: usize = 80;
+const FACET_SIZE: usize = 50;
+
+#[derive(Default)]
+struct Facets {
+    points: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+}
+
+impl Facets {
+    /// Adds a facet as a triangle fan, computing the normal when the file has none.
+    fn push(&mut self, normal: [f32; 3], vertices: &[[f32; 3]]) {
+        for i in 1..vertices.len() - 1 {
+            let triangle = [vertices[0], vertices[i], vertices[i + 1]];
+            let normal = if normal == [0.0; 3] {
+                triangle_normal(triangle[0], triangle[1], triangle[2])
+            } else {
+                normal
+            };
+            self.points.extend_from_slice(&triangle);
+            self.normals.extend_from_slice(&[normal; 3]);
+        }
+    }
+
+    fn build(self) -> Result<F3DMesh> {
+        let count = self.points.len() as u32 / 3;
+        F3DMesh::builder()
+            .points(&self.points)
+            .normals(&self.normals)
+            .faces(
+                &vec![3; count as usize],
+                &(0..count * 3).collect::<Vec<_>>(),
+            )
+            .build()
+    }
+}
+
+/// Reads either flavour, detected from the first bytes.
+pub fn read(mut reader: impl Read) -> Result<F3DMesh> {
+    let mut head = Vec::with_capacity(HEADER_SIZE + 4);
+    (&mut reader)
+        .take((HEADER_SIZE + 4) as u64)
+        .read_to_end(&mut head)?;
+
+    // Binary headers may also start with `solid`, but their facet count is rarely printable.
+    let ascii = head.starts_with(b"solid")
+        && head
+            .iter()
+            .all(|byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace());
+    let reader = Cursor::new(head).chain(reader);
+    if ascii {
+        read_ascii(BufReader::new(reader))
+    } else {
+        read_binary(reader)
+    }
+}
+
+pub fn read_ascii(reader: impl BufRead) -> Result<F3DMesh> {
+    let mut facets = Facets::default();
+    let mut facet: Option<([f32; 3], Vec<[f32; 3]>)> = None;
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let error = |reason: &str| parse_error(FORMAT, format!("line {}: {reason}", number + 1));
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("facet") => {
+                if facet.is_some() {
+                    return Err(error("facet before endfacet"));
+                }
+                if tokens.next() != Some("normal") {
+                    return Err(error("expected `facet normal`"));
+                }
+                let normal = vector(tokens).ok_or_else(|| error("invalid normal"))?;
+                facet = Some((normal, Vec::new()));
+            }
+            Some("vertex") => {
+                let vertex = vector(tokens).ok_or_else(|| error("invalid vertex"))?;
+                facet
+                    .as_mut()
+                    .ok_or_else(|| error("vertex outside of a facet"))?
+                    .1
+                    .push(vertex);
+            }
+            Some("endfacet") => {
+                let (normal, vertices) =
+                    facet.take().ok_or_else(|| error("unexpected endfacet"))?;
+                if vertices.len() < 3 {
+                    return Err(error("facet has fewer than 3 vertices"));
+                }
+                facets.push(normal, &vertices);
+            }
+            _ => {}
+        }
+    }
+
+    if facet.is_some() {
+        return Err(parse_error(FORMAT, "missing endfacet at end of file"));
+    }
+    facets.build()
+}
+
+fn vector<'t>(tokens: impl Iterator<Item = &'t str>) -> Option<[f32; 3]> {
+    let values = tokens
+        .map(|token| token.parse().ok())
+        .collect::<Option<Vec<f32>>>()?;
+    values.try_into().ok()
+}
+
+pub fn read_binary(mut reader: impl Read) -> Result<F3DMesh> {
+    let mut header = [0; HEADER_SIZE + 4];
+    read_exact(&mut reader, &mut header, "truncated header")?;
+    let count = u32::from_le_bytes(header[HEADER_SIZE..].try_into().unwrap());
+
+    let mut facets = Facets::default();
+    let mut record = [0; FACET_SIZE];
+    for index in 0..count {
+        read_exact(
+            &mut reader,
+            &mut record,
+            &format!("expected {count} facets, found {index}"),
+        )?;
+        let values: Vec<f32> = record[..48]
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        let vector = |i: usize| [values[i * 3], values[i * 3 + 1], values[i * 3 + 2]];
+        facets.push(vector(0), &[vector(1), vector(2), vector(3)]);
+    }
+    facets.build()
+}
+
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8], reason: &str) -> Result<()> {
+    reader.read_exact(buffer).map_err(|err| match err.kind() {
+        ErrorKind::UnexpectedEof => parse_error(FORMAT, reason),
+        _ => Error::Io(err),
+    })
+}
+
+/// Triangles of the mesh with their geometric normals; other faces are fanned.
+fn triangles(mesh: &F3DMesh) -> impl Iterator<Item = ([f32; 3], [[f32; 3]; 3])> + '_ {
+    faces(mesh)
+        .filter(|face| face.len() >= 3)
+        .flat_map(move |face| {
+            (1..face.len() - 1).map(move |i| {
+                let triangle = [face[0], face[i], face[i + 1]].map(|index| point(mesh, index));
+                (
+                    triangle_normal(triangle[0], triangle[1], triangle[2]),
+                    triangle,
+                )
+            })
+        })
+}
+
+pub fn write_ascii(mut writer: impl Write, mesh: &F3DMesh, name: &str) -> Result<()> {
+    mesh.validate()?;
+    writeln!(writer, "solid {name}")?;
+    for (normal, triangle) in triangles(mesh) {
+        writeln!(
+            writer,
+            "  facet normal {} {} {}",
+            normal[0], normal[1], normal[2]
+        )?;
+        writeln!(writer, "    outer loop")?;
+        for vertex in triangle {
+            writeln!(
+                writer,
+                "      vertex {} {} {}",
+                vertex[0], vertex[1], vertex[2]
+            )?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+    writeln!(writer, "endsolid {name}")?;
+    Ok(())
+}
+
+pub fn write_binary(mut writer: impl Write, mesh: &F3DMesh) -> Result<()> {
+    mesh.validate()?;
+    let count = u32::try_from(triangles(mesh).count())
+        .map_err(|_| Error::InvalidMesh("too many triangles for binary STL".to_string()))?;
+
+    let mut header = [0; HEADER_SIZE];
+    let title = b"binary STL written by f3d";
+    header[..title.len()].copy_from_slice(title);
+    writer.write_all(&header)?;
+    writer.write_all(&count.to_le_bytes())?;
+
+    for (normal, triangle) in triangles(mesh) {
+        for value in normal.iter().chain(triangle.iter().flatten()) {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&[0, 0])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    fn assert_same_triangles(mesh: &F3DMesh, loaded: &F3DMesh) {
+        let expected: Vec<_> = triangles(mesh).collect();
+        let actual: Vec<_> = triangles(loaded).collect();
+        assert_eq!(expected, actual);
+        assert_eq!(loaded.point_count(), expected.len() * 3);
+        for (i, (normal, _)) in expected.iter().enumerate() {
+            assert_eq!(&loaded.normals()[i * 9..i * 9 + 3], normal);
+        }
+    }
+
+    #[test]
+    fn round_trips_ascii() {
+        let mesh = primitives::cube(1.0);
+        let mut bytes = Vec::new();
+        write_ascii(&mut bytes, &mesh, "cube").unwrap();
+        assert!(bytes.starts_with(b"solid cube\n"));
+        assert_same_triangles(&mesh, &read(bytes.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn round_trips_binary() {
+        let mesh = primitives::cylinder(0.5, 1.0, 8);
+        let mut bytes = Vec::new();
+        write_binary(&mut bytes, &mesh).unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + 4 + 8 * 4 * FACET_SIZE);
+        assert_same_triangles(&mesh, &read(bytes.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn detects_binary_starting_with_solid() {
+        let mesh = primitives::plane(1.0, 1.0);
+        let mut bytes = Vec::new();
+        write_binary(&mut bytes, &mesh).unwrap();
+        bytes[..5].copy_from_slice(b"solid");
+        assert_eq!(read(bytes.as_slice()).unwrap().face_count(), 2);
+    }
+
+    #[test]
+    fn computes_missing_normals() {
+        let source = "\
+solid quad
+facet normal 0 0 0
+outer loop
+vertex 0 0 0
+vertex 1 0 0
+vertex 1 1 0
+vertex 0 1 0
+endloop
+endfacet
+endsolid quad
+";
+        let mesh = read(source.as_bytes()).unwrap();
+        assert_eq!(mesh.face_count(), 2);
+        assert_eq!(mesh.point_count(), 6);
+        assert!(mesh.normals().chunks(3).all(|n| n == [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn reports_truncated_data() {
+        let mut bytes = Vec::new();
+        write_binary(&mut bytes, &primitives::cube(1.0)).unwrap();
+        bytes.truncate(bytes.len() - 10);
+        assert!(matches!(
+            read(bytes.as_slice()),
+            Err(Error::MeshParse { format: "STL", reason }) if reason == "expected 12 facets, found 11"
+        ));
+
+        let err = read("solid x\nfacet normal 0 0 1\nvertex 0 0\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::MeshParse { reason, .. } if reason.starts_with("line 3")));
+    }
+}
@@ -2,11 +2,23 @@ pub mod sys {
     pub use f3d_sys::*;
 }
 
+pub mod batch;
 pub mod camera;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod console;
 pub mod engine;
+pub mod error;
 pub mod image;
 pub mod interactor;
+pub mod io;
 pub mod options;
+pub mod plugins;
+pub mod primitives;
+pub mod reader_options;
+pub mod readers;
 pub mod scene;
 pub mod types;
 pub mod window;
+
+pub use error::{Error, Result};
@@ -1,49 +1,88 @@
+use crate::error::{Error, Result};
 use crate::sys::*;
-use std::ffi::CString;
+use crate::types::{F3DColor, F3DColormap, F3DDirection, F3DRatio, F3DTransform2d};
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::path::PathBuf;
 use std::ptr::NonNull;
+use std::sync::OnceLock;
 
-pub struct Options {
+#[cfg(feature = "serde")]
+mod profile;
+mod schema;
+#[cfg(feature = "serde")]
+pub use profile::{OptionsProfile, ProfileValue};
+pub use schema::*;
+
+// Types libf3d reads and writes as a double, a string or a double vector.
+const NUMBER: &[OptionType] = &[OptionType::Double, OptionType::Ratio];
+const TEXT: &[OptionType] = &[OptionType::String, OptionType::Path];
+const DOUBLES: &[OptionType] = &[
+    OptionType::DoubleVector,
+    OptionType::Color,
+    OptionType::Direction,
+    OptionType::Colormap,
+    OptionType::Transform2d,
+];
+
+/// Option keys mapped to their libf3d string representation.
+pub type OptionsMap = BTreeMap<String, String>;
+
+/// Handle to the options of an [`Engine`](crate::engine::Engine), valid for the engine's lifetime.
+pub struct Options<'a> {
     ptr: NonNull<f3d_options_t>,
+    _owner: PhantomData<&'a ()>,
 }
 
-impl Options {
+impl<'a> Options<'a> {
     pub(crate) unsafe fn from_raw(ptr: *mut f3d_options_t) -> Self {
         Self {
             ptr: NonNull::new(ptr).expect("null f3d_options_t"),
+            _owner: PhantomData,
         }
     }
 
-    pub fn set_bool(&self, key: &str, value: bool) {
-        let ckey = CString::new(key).unwrap();
+    pub fn set_bool(&self, key: &str, value: bool) -> Result<()> {
+        Self::check_access(key, &[OptionType::Bool])?;
+        let ckey = CString::new(key)?;
         unsafe {
             f3d_options_set_as_bool(self.ptr.as_ptr(), ckey.as_ptr(), value as i32);
         }
+        Ok(())
     }
 
-    pub fn set_int(&self, key: &str, value: i32) {
-        let ckey = CString::new(key).unwrap();
+    pub fn set_int(&self, key: &str, value: i32) -> Result<()> {
+        Self::check_access(key, &[OptionType::Int])?;
+        let ckey = CString::new(key)?;
         unsafe {
             f3d_options_set_as_int(self.ptr.as_ptr(), ckey.as_ptr(), value);
         }
+        Ok(())
     }
 
-    pub fn set_double(&self, key: &str, value: f64) {
-        let ckey = CString::new(key).unwrap();
+    pub fn set_double(&self, key: &str, value: f64) -> Result<()> {
+        Self::check_access(key, NUMBER)?;
+        let ckey = CString::new(key)?;
         unsafe {
             f3d_options_set_as_double(self.ptr.as_ptr(), ckey.as_ptr(), value);
         }
+        Ok(())
     }
 
-    pub fn set_string(&self, key: &str, value: &str) {
-        let ckey = CString::new(key).unwrap();
-        let cvalue = CString::new(value).unwrap();
+    pub fn set_string(&self, key: &str, value: &str) -> Result<()> {
+        Self::check_access(key, TEXT)?;
+        let ckey = CString::new(key)?;
+        let cvalue = CString::new(value)?;
         unsafe {
             f3d_options_set_as_string(self.ptr.as_ptr(), ckey.as_ptr(), cvalue.as_ptr());
         }
+        Ok(())
     }
 
-    pub fn set_double_array(&self, key: &str, values: &[f64]) {
-        let ckey = CString::new(key).unwrap();
+    pub fn set_double_array(&self, key: &str, values: &[f64]) -> Result<()> {
+        Self::check_access(key, DOUBLES)?;
+        let ckey = CString::new(key)?;
         unsafe {
             f3d_options_set_as_double_vector(
                 self.ptr.as_ptr(),
@@ -52,10 +91,12 @@ impl Options {
                 values.len() as usize,
             );
         }
+        Ok(())
     }
 
-    pub fn set_int_array(&self, key: &str, values: &[i32]) {
-        let ckey = CString::new(key).unwrap();
+    pub fn set_int_array(&self, key: &str, values: &[i32]) -> Result<()> {
+        Self::check_access(key, &[OptionType::IntVector])?;
+        let ckey = CString::new(key)?;
         unsafe {
             f3d_options_set_as_int_vector(
                 self.ptr.as_ptr(),
@@ -64,46 +105,77 @@ impl Options {
                 values.len() as usize,
             );
         }
+        Ok(())
     }
 
-    pub fn get_bool(&self, key: &str) -> bool {
-        let ckey = CString::new(key).unwrap();
-        unsafe { f3d_options_get_as_bool(self.ptr.as_ptr(), ckey.as_ptr()) != 0 }
+    pub fn get_bool(&self, key: &str) -> Result<bool> {
+        Self::check_access(key, &[OptionType::Bool])?;
+        let ckey = CString::new(key)?;
+        unsafe { Ok(f3d_options_get_as_bool(self.ptr.as_ptr(), ckey.as_ptr()) != 0) }
     }
 
-    pub fn get_int(&self, key: &str) -> i32 {
-        let ckey = CString::new(key).unwrap();
-        unsafe { f3d_options_get_as_int(self.ptr.as_ptr(), ckey.as_ptr()) }
+    pub fn get_int(&self, key: &str) -> Result<i32> {
+        Self::check_access(key, &[OptionType::Int])?;
+        let ckey = CString::new(key)?;
+        unsafe { Ok(f3d_options_get_as_int(self.ptr.as_ptr(), ckey.as_ptr())) }
     }
 
-    pub fn get_double(&self, key: &str) -> f64 {
-        let ckey = CString::new(key).unwrap();
-        unsafe { f3d_options_get_as_double(self.ptr.as_ptr(), ckey.as_ptr()) }
+    pub fn get_double(&self, key: &str) -> Result<f64> {
+        Self::check_access(key, NUMBER)?;
+        let ckey = CString::new(key)?;
+        unsafe { Ok(f3d_options_get_as_double(self.ptr.as_ptr(), ckey.as_ptr())) }
     }
 
-    pub fn get_string(&self, key: &str) -> String {
-        let ckey = CString::new(key).unwrap();
+    pub fn get_string(&self, key: &str) -> Result<String> {
+        Self::check_access(key, TEXT)?;
+        let ckey = CString::new(key)?;
         unsafe {
-            let cstr = f3d_options_get_as_string(self.ptr.as_ptr(), ckey.as_ptr()) as *mut i8;
-            let result = CString::from_raw(cstr).to_string_lossy().into_owned();
+            let cstr = f3d_options_get_as_string(self.ptr.as_ptr(), ckey.as_ptr());
+            if cstr.is_null() {
+                return Err(self.access_error(key, "string"));
+            }
+            let result = CStr::from_ptr(cstr).to_string_lossy().into_owned();
             f3d_options_free_string(cstr);
-            result
+            Ok(result)
         }
     }
 
-    pub fn get_string_representation(&self, key: &str) -> String {
-        let ckey = CString::new(key).unwrap();
+    pub fn get_string_representation(&self, key: &str) -> Result<String> {
+        let ckey = CString::new(key)?;
         unsafe {
-            let cstr = f3d_options_get_as_string_representation(self.ptr.as_ptr(), ckey.as_ptr())
-                as *mut i8;
-            let result = CString::from_raw(cstr).into_string().unwrap();
+            let cstr = f3d_options_get_as_string_representation(self.ptr.as_ptr(), ckey.as_ptr());
+            if cstr.is_null() {
+                return Err(Error::InvalidOptionKey(key.to_string()));
+            }
+            let result = CStr::from_ptr(cstr).to_string_lossy().into_owned();
             f3d_options_free_string(cstr);
-            result
+            Ok(result)
+        }
+    }
+
+    /// Parses `value` the same way libf3d parses config files and command lines.
+    pub fn set_string_representation(&self, key: &str, value: &str) -> Result<()> {
+        let ckey = CString::new(key)?;
+        let cvalue = CString::new(value)?;
+        let status = unsafe {
+            f3d_options_set_as_string_representation(
+                self.ptr.as_ptr(),
+                ckey.as_ptr(),
+                cvalue.as_ptr(),
+            )
+        };
+        if status == 0 {
+            let expected = Options::type_of(key)
+                .map(|value_type| value_type.to_string())
+                .unwrap_or_else(|_| "string representation".to_string());
+            return Err(self.access_error(key, &expected));
         }
+        Ok(())
     }
 
-    pub fn get_double_array(&self, key: &str) -> Vec<f64> {
-        let ckey = CString::new(key).unwrap();
+    pub fn get_double_array(&self, key: &str) -> Result<Vec<f64>> {
+        Self::check_access(key, DOUBLES)?;
+        let ckey = CString::new(key)?;
         let mut count: usize = 0;
         unsafe {
             f3d_options_get_as_double_vector(
@@ -121,12 +193,13 @@ impl Options {
                     &mut count as *mut usize,
                 );
             }
-            values
+            Ok(values)
         }
     }
 
-    pub fn get_int_array(&self, key: &str) -> Vec<i32> {
-        let ckey = CString::new(key).unwrap();
+    pub fn get_int_array(&self, key: &str) -> Result<Vec<i32>> {
+        Self::check_access(key, &[OptionType::IntVector])?;
+        let ckey = CString::new(key)?;
         let mut count: usize = 0;
         unsafe {
             f3d_options_get_as_int_vector(
@@ -144,7 +217,457 @@ impl Options {
                     &mut count as *mut usize,
                 );
             }
-            values
+            Ok(values)
+        }
+    }
+
+    /// Fails unless libf3d knows `key`.
+    fn check_key(key: &str) -> Result<()> {
+        static KEYS: OnceLock<Vec<String>> = OnceLock::new();
+        let keys = KEYS.get_or_init(|| {
+            let mut keys = Options::keys();
+            keys.sort();
+            keys
+        });
+        match keys.binary_search_by(|known| known.as_str().cmp(key)) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::InvalidOptionKey(key.to_string())),
+        }
+    }
+
+    /// Also fails when the schema gives `key` a type outside `accepted`, whose first
+    /// entry names the expected type. libf3d ignores such accesses silently.
+    fn check_access(key: &str, accepted: &[OptionType]) -> Result<()> {
+        Self::check_key(key)?;
+        match schema().binary_search_by_key(&key, |info| info.key) {
+            Ok(index) if !accepted.contains(&schema()[index].value_type) => {
+                Err(Error::InvalidOptionType {
+                    key: key.to_string(),
+                    expected: accepted[0].to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Distinguishes an unknown key from a key holding a value of another type.
+    fn access_error(&self, key: &str, expected: &str) -> Error {
+        match self.get_string_representation(key) {
+            Ok(_) => Error::InvalidOptionType {
+                key: key.to_string(),
+                expected: expected.to_string(),
+            },
+            Err(err) => err,
+        }
+    }
+
+    /// Names of all options known to libf3d.
+    pub fn keys() -> Vec<String> {
+        let mut count: usize = 0;
+        unsafe {
+            let names = f3d_options_get_all_names(&mut count as *mut usize);
+            if names.is_null() {
+                return Vec::new();
+            }
+
+            let keys = std::slice::from_raw_parts(names, count)
+                .iter()
+                .filter(|name| !name.is_null())
+                .map(|&name| CStr::from_ptr(name).to_string_lossy().into_owned())
+                .collect();
+
+            f3d_options_free_names(names, count);
+
+            keys
+        }
+    }
+
+    /// Value type of `key`, as described by the option schema.
+    ///
+    /// Keys libf3d knows but the schema does not are probed on scratch options. The C API
+    /// only exposes how values are stored, so those report `Double`, `String` or
+    /// `DoubleVector` for every type accessed through the same getters.
+    pub fn type_of(key: &str) -> Result<OptionType> {
+        Self::check_key(key)?;
+        match schema().binary_search_by_key(&key, |info| info.key) {
+            Ok(index) => Ok(schema()[index].value_type),
+            Err(_) => Self::probe_type(key).ok_or_else(|| Error::InvalidOptionKey(key.to_string())),
+        }
+    }
+
+    /// Finds the setter that changes the value of `key`; libf3d ignores the others.
+    fn probe_type(key: &str) -> Option<OptionType> {
+        let ckey = CString::new(key).ok()?;
+        let raw = NonNull::new(unsafe { f3d_options_create() })?.as_ptr();
+        let scratch = unsafe { Options::from_raw(raw) };
+        // Two distinct values, since the first one may already be the default.
+        let accepts = |set: &dyn Fn(bool)| {
+            set(false);
+            let first = scratch.get_string_representation(key).ok();
+            set(true);
+            first != scratch.get_string_representation(key).ok()
+        };
+        let key = ckey.as_ptr();
+
+        let found = unsafe {
+            if accepts(&|second| {
+                f3d_options_set_as_bool(raw, key, second as i32);
+            }) {
+                Some(OptionType::Bool)
+            } else if accepts(&|second| {
+                f3d_options_set_as_int(raw, key, 1 + second as i32);
+            }) {
+                Some(OptionType::Int)
+            } else if accepts(&|second| {
+                f3d_options_set_as_double(raw, key, if second { 0.5 } else { 0.25 });
+            }) {
+                Some(OptionType::Double)
+            } else if accepts(&|second| {
+                f3d_options_set_as_string(raw, key, if second { c"b" } else { c"a" }.as_ptr());
+            }) {
+                Some(OptionType::String)
+            } else if accepts(&|second| {
+                let mut values = [1 + second as i32; 3];
+                f3d_options_set_as_int_vector(raw, key, values.as_mut_ptr(), values.len());
+            }) {
+                Some(OptionType::IntVector)
+            } else if [3, 4, 9].into_iter().any(|len| {
+                // Colors and directions take 3 values, colormaps 4 per entry, transforms 9.
+                accepts(&|second| {
+                    let mut values = vec![if second { 0.5 } else { 0.25 }; len];
+                    f3d_options_set_as_double_vector(raw, key, values.as_mut_ptr(), len);
+                })
+            }) {
+                Some(OptionType::DoubleVector)
+            } else {
+                None
+            }
+        };
+        unsafe { f3d_options_delete(raw) };
+        found
+    }
+
+    pub fn is_optional(&self, key: &str) -> Result<bool> {
+        Self::check_key(key)?;
+        let ckey = CString::new(key)?;
+        unsafe { Ok(f3d_options_is_optional(self.ptr.as_ptr(), ckey.as_ptr()) != 0) }
+    }
+
+    pub fn has_value(&self, key: &str) -> Result<bool> {
+        Self::check_key(key)?;
+        let ckey = CString::new(key)?;
+        unsafe { Ok(f3d_options_has_value(self.ptr.as_ptr(), ckey.as_ptr()) != 0) }
+    }
+
+    pub fn remove_value(&self, key: &str) -> Result<()> {
+        Self::check_key(key)?;
+        let ckey = CString::new(key)?;
+        unsafe {
+            f3d_options_remove_value(self.ptr.as_ptr(), ckey.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Restores the default value of `key`.
+    pub fn reset(&self, key: &str) -> Result<()> {
+        Self::check_key(key)?;
+        let ckey = CString::new(key)?;
+        unsafe {
+            f3d_options_reset(self.ptr.as_ptr(), ckey.as_ptr());
+        }
+        Ok(())
+    }
+
+    pub fn is_same(&self, other: &Options, key: &str) -> Result<bool> {
+        Self::check_key(key)?;
+        let ckey = CString::new(key)?;
+        unsafe {
+            Ok(f3d_options_is_same(self.ptr.as_ptr(), other.ptr.as_ptr(), ckey.as_ptr()) != 0)
+        }
+    }
+
+    /// Keys whose values differ between `self` and `other`.
+    pub fn diff(&self, other: &Options) -> Result<Vec<OptionDiff>> {
+        let mut diffs = Vec::new();
+        for key in Options::keys() {
+            if self.is_same(other, &key)? {
+                continue;
+            }
+            diffs.push(OptionDiff {
+                ours: self.value_repr(&key)?,
+                theirs: other.value_repr(&key)?,
+                key,
+            });
+        }
+        Ok(diffs)
+    }
+
+    /// Every option holding a value, in its string representation.
+    ///
+    /// Unset optionals are left out, so applying the map does not clear them;
+    /// `OptionsProfile` records them explicitly.
+    pub fn to_map(&self) -> Result<OptionsMap> {
+        let mut map = OptionsMap::new();
+        for key in Options::keys() {
+            if let Some(value) = self.value_repr(&key)? {
+                map.insert(key, value);
+            }
+        }
+        Ok(map)
+    }
+
+    /// Sets every option of `map`, stopping at the first invalid entry.
+    pub fn apply_map(&self, map: &OptionsMap) -> Result<()> {
+        for (key, value) in map {
+            self.set_string_representation(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// String representation of `key`, or `None` for an unset optional.
+    fn value_repr(&self, key: &str) -> Result<Option<String>> {
+        if !self.has_value(key)? {
+            return Ok(None);
+        }
+        self.get_string_representation(key).map(Some)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+    Bool,
+    Int,
+    Double,
+    Ratio,
+    String,
+    Path,
+    Color,
+    Direction,
+    Colormap,
+    Transform2d,
+    DoubleVector,
+    IntVector,
+}
+
+impl std::fmt::Display for OptionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OptionType::Bool => "bool",
+            OptionType::Int => "int",
+            OptionType::Double => "double",
+            OptionType::Ratio => "ratio",
+            OptionType::String => "string",
+            OptionType::Path => "path",
+            OptionType::Color => "color",
+            OptionType::Direction => "direction",
+            OptionType::Colormap => "colormap",
+            OptionType::Transform2d => "transform2d",
+            OptionType::DoubleVector => "vector<double>",
+            OptionType::IntVector => "vector<int>",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OptionInfo {
+    pub key: &'static str,
+    pub value_type: OptionType,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionDiff {
+    pub key: String,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+/// A Rust type that an option value can be read as and written from.
+pub trait OptionValue: Sized {
+    const TYPE: OptionType;
+
+    fn get(options: &Options, key: &str) -> Result<Self>;
+    fn set(self, options: &Options, key: &str) -> Result<()>;
+}
+
+impl OptionValue for bool {
+    const TYPE: OptionType = OptionType::Bool;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_bool(key)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_bool(key, self)
+    }
+}
+
+impl OptionValue for i32 {
+    const TYPE: OptionType = OptionType::Int;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_int(key)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_int(key, self)
+    }
+}
+
+impl OptionValue for f64 {
+    const TYPE: OptionType = OptionType::Double;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_double(key)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_double(key, self)
+    }
+}
+
+impl OptionValue for F3DRatio {
+    const TYPE: OptionType = OptionType::Ratio;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_double(key).map(F3DRatio::new)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_double(key, self.value)
+    }
+}
+
+impl OptionValue for String {
+    const TYPE: OptionType = OptionType::String;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_string(key)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_string(key, &self)
+    }
+}
+
+impl OptionValue for PathBuf {
+    const TYPE: OptionType = OptionType::Path;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_string(key).map(PathBuf::from)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        // A lossy conversion would silently point libf3d at another file.
+        let path = self.to_str().ok_or_else(|| Error::InvalidOptionType {
+            key: key.to_string(),
+            expected: "UTF-8 path".to_string(),
+        })?;
+        options.set_string(key, path)
+    }
+}
+
+impl OptionValue for Vec<f64> {
+    const TYPE: OptionType = OptionType::DoubleVector;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_double_array(key)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_double_array(key, &self)
+    }
+}
+
+impl OptionValue for Vec<i32> {
+    const TYPE: OptionType = OptionType::IntVector;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options.get_int_array(key)
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_int_array(key, &self)
+    }
+}
+
+impl OptionValue for F3DColormap {
+    const TYPE: OptionType = OptionType::Colormap;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        options
+            .get_double_array(key)
+            .map(|data| F3DColormap { data })
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_double_array(key, &self.data)
+    }
+}
+
+/// Reads a fixed-size double vector, rejecting values of another length.
+fn get_fixed<const N: usize>(options: &Options, key: &str, expected: &str) -> Result<[f64; N]> {
+    options
+        .get_double_array(key)?
+        .try_into()
+        .map_err(|_| Error::InvalidOptionType {
+            key: key.to_string(),
+            expected: expected.to_string(),
+        })
+}
+
+impl OptionValue for F3DColor {
+    const TYPE: OptionType = OptionType::Color;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        get_fixed(options, key, "color").map(|data| F3DColor { data })
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_double_array(key, &self.data)
+    }
+}
+
+impl OptionValue for F3DDirection {
+    const TYPE: OptionType = OptionType::Direction;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        get_fixed(options, key, "direction").map(|data| F3DDirection { data })
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_double_array(key, &self.data)
+    }
+}
+
+impl OptionValue for F3DTransform2d {
+    const TYPE: OptionType = OptionType::Transform2d;
+
+    fn get(options: &Options, key: &str) -> Result<Self> {
+        get_fixed(options, key, "transform2d").map(|data| F3DTransform2d { data })
+    }
+
+    fn set(self, options: &Options, key: &str) -> Result<()> {
+        options.set_double_array(key, &self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_types_of_libf3d_keys() {
+        for key in Options::keys() {
+            let value_type = Options::type_of(&key).unwrap();
+            if let Ok(index) = schema().binary_search_by_key(&key.as_str(), |info| info.key) {
+                assert_eq!(value_type, schema()[index].value_type, "{key}");
+            }
         }
+        assert!(matches!(
+            Options::type_of("render.no_such_option"),
+            Err(Error::InvalidOptionKey(_))
+        ));
     }
 }
@@ -0,0 +1,159 @@
+//! Serde support for option presets.
+//!
+//! A profile is a flat document keyed by option name, the same shape as the
+//! `options` blocks of f3d configuration files:
+//!
+//! ```json
+//! { "render.grid.enable": true, "render.background.color": "0.1,0.1,0.1", "render.line_width": null }
+//! ```
+//!
+//! `null` clears an optional option. TOML has no null, so TOML profiles leave
+//! unset optionals out and applying them keeps their current value.
+
+use super::{OptionType, Options, OptionsMap};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A single option value; scalars keep their JSON/TOML type, everything else
+/// uses the libf3d string representation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProfileValue {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    String(String),
+    /// An optional option without a value, written as `null`.
+    #[serde(serialize_with = "serialize_unset")]
+    Unset,
+}
+
+/// Serializes as a missing value, which TOML maps skip instead of failing on.
+fn serialize_unset<S: serde::Serializer>(serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_none()
+}
+
+impl ProfileValue {
+    fn from_repr(key: &str, repr: String) -> Self {
+        let parsed = match Options::type_of(key) {
+            Ok(OptionType::Bool) => repr.parse().ok().map(ProfileValue::Bool),
+            Ok(OptionType::Int) => repr.parse().ok().map(ProfileValue::Int),
+            Ok(OptionType::Double | OptionType::Ratio) => {
+                repr.parse().ok().map(ProfileValue::Double)
+            }
+            _ => None,
+        };
+        parsed.unwrap_or(ProfileValue::String(repr))
+    }
+
+    fn to_repr(&self) -> Option<String> {
+        match self {
+            ProfileValue::Bool(value) => Some(value.to_string()),
+            ProfileValue::Int(value) => Some(value.to_string()),
+            ProfileValue::Double(value) => Some(value.to_string()),
+            ProfileValue::String(value) => Some(value.clone()),
+            ProfileValue::Unset => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OptionsProfile {
+    pub options: BTreeMap<String, ProfileValue>,
+}
+
+impl OptionsProfile {
+    /// Captures every option of `options`, unset optionals as [`ProfileValue::Unset`].
+    pub fn capture(options: &Options) -> Result<Self> {
+        let mut profile = Self::from_map(options.to_map()?);
+        for key in Options::keys() {
+            if !profile.options.contains_key(&key) && options.is_optional(&key)? {
+                profile.options.insert(key, ProfileValue::Unset);
+            }
+        }
+        Ok(profile)
+    }
+
+    /// Sets every option of the profile and clears the unset ones.
+    pub fn apply(&self, options: &Options) -> Result<()> {
+        options.apply_map(&self.to_map())?;
+        for (key, value) in &self.options {
+            if *value == ProfileValue::Unset {
+                options.remove_value(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn from_map(map: OptionsMap) -> Self {
+        let options = map
+            .into_iter()
+            .map(|(key, repr)| {
+                let value = ProfileValue::from_repr(&key, repr);
+                (key, value)
+            })
+            .collect();
+        Self { options }
+    }
+
+    /// String representations of the options holding a value; unset entries are left out.
+    pub fn to_map(&self) -> OptionsMap {
+        self.options
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.to_repr()?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> OptionsProfile {
+        OptionsProfile {
+            options: BTreeMap::from([
+                ("render.grid.enable".to_string(), ProfileValue::Bool(true)),
+                (
+                    "render.grid.subdivisions".to_string(),
+                    ProfileValue::Int(10),
+                ),
+                ("render.line_width".to_string(), ProfileValue::Unset),
+                ("render.point_size".to_string(), ProfileValue::Double(2.5)),
+                (
+                    "render.background.color".to_string(),
+                    ProfileValue::String("0.1,0.2,0.3".to_string()),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let json = serde_json::to_string(&profile()).unwrap();
+        assert!(json.contains(r#""render.line_width":null"#));
+        assert!(json.contains(r#""render.point_size":2.5"#));
+        let parsed: OptionsProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, profile());
+    }
+
+    #[test]
+    fn round_trips_through_toml_without_unset_options() {
+        let toml = toml::to_string(&profile()).unwrap();
+        assert!(!toml.contains("line_width"));
+        let parsed: OptionsProfile = toml::from_str(&toml).unwrap();
+        let mut expected = profile();
+        expected.options.remove("render.line_width");
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn leaves_unset_options_out_of_maps() {
+        let map = profile().to_map();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map["render.grid.enable"], "true");
+        assert_eq!(map["render.point_size"], "2.5");
+        assert!(!map.contains_key("render.line_width"));
+    }
+}
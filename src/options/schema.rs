@@ -0,0 +1,380 @@
+//! Typed accessors for the libf3d options.
+//!
+//! The schema below is maintained by hand from libf3d's `options.json`; the
+//! tests check it against the keys reported by the linked libf3d.
+//!
+//! Every option is reachable through nested groups mirroring its dotted key,
+//! e.g. `options.render().grid().set_enable(true)` writes `render.grid.enable`.
+//! Optional options read back as `Option<T>` and can be cleared with `unset_*`.
+
+macro_rules! option_key {
+    ([$($path:ident)*] $group:ident $field:ident) => {
+        concat!($(stringify!($path), ".",)* stringify!($group), ".", stringify!($field))
+    };
+    ([$($path:ident)*] $group:ident $field:ident $key:literal) => {
+        concat!($(stringify!($path), ".",)* stringify!($group), ".", $key)
+    };
+}
+
+macro_rules! option_leaf {
+    (required $prefix:tt $group:ident $field:ident [$($key:literal)?] $ty:ty) => {
+        paste::paste! {
+            pub fn $field(&self) -> $crate::error::Result<$ty> {
+                <$ty as $crate::options::OptionValue>::get(
+                    self.options,
+                    option_key!($prefix $group $field $($key)?),
+                )
+            }
+
+            pub fn [<set_ $field>](&self, value: impl Into<$ty>) -> $crate::error::Result<()> {
+                $crate::options::OptionValue::set(
+                    value.into(),
+                    self.options,
+                    option_key!($prefix $group $field $($key)?),
+                )
+            }
+        }
+    };
+    (optional $prefix:tt $group:ident $field:ident [$($key:literal)?] $ty:ty) => {
+        paste::paste! {
+            pub fn $field(&self) -> $crate::error::Result<Option<$ty>> {
+                let key = option_key!($prefix $group $field $($key)?);
+                if !self.options.has_value(key)? {
+                    return Ok(None);
+                }
+                <$ty as $crate::options::OptionValue>::get(self.options, key).map(Some)
+            }
+
+            pub fn [<set_ $field>](&self, value: impl Into<$ty>) -> $crate::error::Result<()> {
+                $crate::options::OptionValue::set(
+                    value.into(),
+                    self.options,
+                    option_key!($prefix $group $field $($key)?),
+                )
+            }
+
+            pub fn [<unset_ $field>](&self) -> $crate::error::Result<()> {
+                self.options
+                    .remove_value(option_key!($prefix $group $field $($key)?))
+            }
+        }
+    };
+}
+
+macro_rules! option_group {
+    (
+        $prefix:tt $name:ident {
+            $(required { $($req:ident $(($req_key:literal))? : $req_ty:ty),* $(,)? })?
+            $(optional { $($opt:ident $(($opt_key:literal))? : $opt_ty:ty),* $(,)? })?
+            $(group $sub:ident { $($sub_body:tt)* })*
+        }
+    ) => {
+        pub mod $name {
+            pub struct Group<'o, 'a> {
+                pub(crate) options: &'o $crate::options::Options<'a>,
+            }
+
+            impl<'o, 'a> Group<'o, 'a> {
+                $($(option_leaf!(required $prefix $name $req [$($req_key)?] $req_ty);)*)?
+                $($(option_leaf!(optional $prefix $name $opt [$($opt_key)?] $opt_ty);)*)?
+
+                $(
+                    pub fn $sub(&self) -> $sub::Group<'o, 'a> {
+                        $sub::Group {
+                            options: self.options,
+                        }
+                    }
+                )*
+            }
+
+            pub(crate) fn collect(out: &mut Vec<$crate::options::OptionInfo>) {
+                $($(out.push($crate::options::OptionInfo {
+                    key: option_key!($prefix $name $req $($req_key)?),
+                    value_type: <$req_ty as $crate::options::OptionValue>::TYPE,
+                    optional: false,
+                });)*)?
+                $($(out.push($crate::options::OptionInfo {
+                    key: option_key!($prefix $name $opt $($opt_key)?),
+                    value_type: <$opt_ty as $crate::options::OptionValue>::TYPE,
+                    optional: true,
+                });)*)?
+                $($sub::collect(out);)*
+            }
+
+            $(option_group!(@nested $prefix $name $sub { $($sub_body)* });)*
+        }
+    };
+    (@nested [$($path:ident)*] $parent:ident $name:ident { $($body:tt)* }) => {
+        option_group!([$($path)* $parent] $name { $($body)* });
+    };
+}
+
+macro_rules! options_schema {
+    ($(group $name:ident { $($body:tt)* })*) => {
+        $(option_group!([] $name { $($body)* });)*
+
+        /// Every option known to the schema, in key order.
+        pub fn schema() -> &'static [$crate::options::OptionInfo] {
+            static SCHEMA: std::sync::OnceLock<Vec<$crate::options::OptionInfo>> =
+                std::sync::OnceLock::new();
+            SCHEMA.get_or_init(|| {
+                let mut entries = Vec::new();
+                $($name::collect(&mut entries);)*
+                entries.sort_by_key(|info| info.key);
+                entries
+            })
+        }
+
+        impl<'a> $crate::options::Options<'a> {
+            $(
+                pub fn $name(&self) -> $name::Group<'_, 'a> {
+                    $name::Group { options: self }
+                }
+            )*
+        }
+    };
+}
+
+options_schema! {
+    group interactor {
+        required {
+            invert_zoom: bool,
+            trackball: bool,
+        }
+    }
+
+    group model {
+        optional {
+            textures_transform: crate::types::F3DTransform2d,
+        }
+
+        group color {
+            optional {
+                opacity: crate::types::F3DRatio,
+                rgb: crate::types::F3DColor,
+                texture: std::path::PathBuf,
+            }
+        }
+
+        group emissive {
+            optional {
+                factor: crate::types::F3DColor,
+                texture: std::path::PathBuf,
+            }
+        }
+
+        group matcap {
+            optional {
+                texture: std::path::PathBuf,
+            }
+        }
+
+        group material {
+            optional {
+                base_ior: f64,
+                metallic: crate::types::F3DRatio,
+                roughness: crate::types::F3DRatio,
+                texture: std::path::PathBuf,
+            }
+        }
+
+        group normal {
+            optional {
+                scale: f64,
+                texture: std::path::PathBuf,
+            }
+        }
+
+        group point_sprites {
+            required {
+                enable: bool,
+                size: f64,
+                sprite_type("type"): String,
+            }
+        }
+
+        group scivis {
+            required {
+                cells: bool,
+                colormap: crate::types::F3DColormap,
+                component: i32,
+                enable: bool,
+            }
+            optional {
+                array_name: String,
+                discretization: i32,
+                range: Vec<f64>,
+            }
+        }
+
+        group volume {
+            required {
+                enable: bool,
+                inverse: bool,
+            }
+        }
+    }
+
+    group render {
+        optional {
+            backface_type: String,
+            line_width: f64,
+            point_size: f64,
+            show_edges: bool,
+        }
+
+        group armature {
+            required {
+                enable: bool,
+            }
+        }
+
+        group axes_grid {
+            required {
+                enable: bool,
+            }
+        }
+
+        group background {
+            required {
+                color: crate::types::F3DColor,
+                skybox: bool,
+            }
+
+            group blur {
+                required {
+                    coc: f64,
+                    enable: bool,
+                }
+            }
+        }
+
+        group effect {
+            required {
+                ambient_occlusion: bool,
+                tone_mapping: bool,
+                translucency_support: bool,
+            }
+            optional {
+                final_shader: String,
+            }
+
+            group antialiasing {
+                required {
+                    enable: bool,
+                    mode: String,
+                }
+            }
+        }
+
+        group grid {
+            required {
+                absolute: bool,
+                color: crate::types::F3DColor,
+                enable: bool,
+                subdivisions: i32,
+            }
+            optional {
+                unit: f64,
+            }
+        }
+
+        group hdri {
+            required {
+                ambient: bool,
+            }
+            optional {
+                file: std::path::PathBuf,
+            }
+        }
+
+        group light {
+            required {
+                intensity: crate::types::F3DRatio,
+            }
+        }
+
+        group raytracing {
+            required {
+                denoise: bool,
+                enable: bool,
+                samples: i32,
+            }
+        }
+    }
+
+    group scene {
+        required {
+            up_direction: crate::types::F3DDirection,
+        }
+        optional {
+            force_reader: String,
+        }
+
+        group animation {
+            required {
+                autoplay: bool,
+                frame_rate: f64,
+                indices: Vec<i32>,
+                speed_factor: crate::types::F3DRatio,
+            }
+            optional {
+                time: f64,
+            }
+        }
+
+        group camera {
+            optional {
+                index: i32,
+                orthographic: bool,
+            }
+        }
+    }
+
+    group ui {
+        required {
+            animation_progress: bool,
+            axis: bool,
+            cheatsheet: bool,
+            console: bool,
+            filename: bool,
+            filename_info: String,
+            fps: bool,
+            hdri_filename: bool,
+            loader_progress: bool,
+            metadata: bool,
+            minimal_console: bool,
+            scalar_bar: bool,
+            scale: crate::types::F3DRatio,
+        }
+        optional {
+            font_file: std::path::PathBuf,
+        }
+
+        group backdrop {
+            required {
+                opacity: crate::types::F3DRatio,
+            }
+        }
+
+        group dropzone {
+            required {
+                enable: bool,
+                info: String,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::{Options, schema};
+
+    #[test]
+    fn matches_libf3d_keys() {
+        let mut keys = Options::keys();
+        keys.sort();
+        let described: Vec<&str> = schema().iter().map(|info| info.key).collect();
+        assert_eq!(described, keys);
+    }
+}
@@ -0,0 +1,241 @@
+//! Plugin discovery and loading with per-plugin feedback.
+//!
+//! libf3d only reports whether a plugin loaded, so the readers a plugin
+//! provides are found by comparing [`ReaderRegistry`] snapshots taken before
+//! and after loading it.
+
+use crate::engine::{Engine, F3DReaderInfo};
+use crate::error::Result;
+use crate::readers::ReaderRegistry;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// A plugin listed by the JSON manifests of a search path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredPlugin {
+    pub name: String,
+    /// Search path holding the manifest, `None` for statically linked plugins.
+    pub search_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadedPlugin {
+    pub name: String,
+    /// Library path passed to libf3d, or the bare name when it was resolved by libf3d.
+    pub source: String,
+    /// Readers that appeared when the plugin was loaded.
+    pub readers: Vec<F3DReaderInfo>,
+}
+
+#[derive(Debug)]
+pub struct PluginLoadResult {
+    pub name: String,
+    pub outcome: Result<()>,
+    pub readers: Vec<F3DReaderInfo>,
+}
+
+impl PluginLoadResult {
+    pub fn is_success(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PluginManager {
+    search_paths: Vec<PathBuf>,
+    loaded: Vec<LoadedPlugin>,
+}
+
+impl PluginManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directories scanned for `<name>.json` manifests, also tried first for the plugin libraries.
+    pub fn add_search_path(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    /// Plugins of every search path, the first path listing a name winning.
+    pub fn discover(&self) -> Result<Vec<DiscoveredPlugin>> {
+        let mut plugins: Vec<DiscoveredPlugin> = Vec::new();
+        for path in &self.search_paths {
+            for name in Engine::get_plugins_list(&path.to_string_lossy())? {
+                if plugins.iter().any(|plugin| plugin.name == name) {
+                    continue;
+                }
+                let search_path = has_manifest(path, &name).then(|| path.clone());
+                plugins.push(DiscoveredPlugin { name, search_path });
+            }
+        }
+        Ok(plugins)
+    }
+
+    /// Loads a plugin by name or library path; loading it again returns the first load.
+    pub fn load(&mut self, name_or_path: &str) -> Result<&LoadedPlugin> {
+        let name = plugin_name(name_or_path);
+        if let Some(index) = self.loaded.iter().position(|plugin| plugin.name == name) {
+            return Ok(&self.loaded[index]);
+        }
+
+        let source = self
+            .search_paths
+            .iter()
+            .map(|path| path.join(library_file_name(&name)))
+            .find(|path| path.is_file())
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| name_or_path.to_string());
+
+        let before = ReaderRegistry::current();
+        Engine::load_plugin(&source)?;
+        let readers = added_readers(&before, ReaderRegistry::current());
+
+        self.loaded.push(LoadedPlugin {
+            name,
+            source,
+            readers,
+        });
+        Ok(self.loaded.last().unwrap())
+    }
+
+    /// Loads every discovered plugin individually, continuing past failures.
+    pub fn load_discovered(&mut self) -> Result<Vec<PluginLoadResult>> {
+        let discovered = self.discover()?;
+        Ok(discovered
+            .iter()
+            .map(|plugin| match self.load(&plugin.name) {
+                Ok(loaded) => PluginLoadResult {
+                    name: plugin.name.clone(),
+                    outcome: Ok(()),
+                    readers: loaded.readers.clone(),
+                },
+                Err(err) => PluginLoadResult {
+                    name: plugin.name.clone(),
+                    outcome: Err(err),
+                    readers: Vec::new(),
+                },
+            })
+            .collect())
+    }
+
+    /// Runs [`Engine::auto_load_plugins`] and records the plugins whose readers appeared.
+    pub fn auto_load(&mut self) -> &[LoadedPlugin] {
+        let start = self.loaded.len();
+        let before = ReaderRegistry::current();
+        Engine::auto_load_plugins();
+        for reader in added_readers(&before, ReaderRegistry::current()) {
+            match self.loaded[start..]
+                .iter_mut()
+                .find(|plugin| plugin.name == reader.plugin_name)
+            {
+                Some(plugin) => plugin.readers.push(reader),
+                None => self.loaded.push(LoadedPlugin {
+                    name: reader.plugin_name.clone(),
+                    source: reader.plugin_name.clone(),
+                    readers: vec![reader],
+                }),
+            }
+        }
+        &self.loaded[start..]
+    }
+
+    /// Plugins loaded through this manager, in load order.
+    pub fn loaded(&self) -> &[LoadedPlugin] {
+        &self.loaded
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.loaded.iter().any(|plugin| plugin.name == name)
+    }
+
+    /// Plugins providing readers right now, including static ones and those loaded elsewhere.
+    pub fn active_plugins() -> BTreeSet<String> {
+        ReaderRegistry::current()
+            .plugins()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+}
+
+fn added_readers(before: &ReaderRegistry, after: ReaderRegistry) -> Vec<F3DReaderInfo> {
+    after
+        .readers()
+        .iter()
+        .filter(|reader| before.get(&reader.name).is_none())
+        .cloned()
+        .collect()
+}
+
+fn has_manifest(search_path: &Path, name: &str) -> bool {
+    search_path.join(format!("{name}.json")).is_file()
+}
+
+/// Platform file name of a plugin library, e.g. `libf3d-plugin-assimp.so`.
+fn library_file_name(name: &str) -> String {
+    format!(
+        "{}f3d-plugin-{name}{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    )
+}
+
+/// Plugin name of a library path, or the argument itself when it is already a name.
+fn plugin_name(name_or_path: &str) -> String {
+    let path = Path::new(name_or_path);
+    if path.components().count() == 1 && path.extension().is_none() {
+        return name_or_path.to_string();
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let stem = stem
+        .strip_prefix(std::env::consts::DLL_PREFIX)
+        .unwrap_or(&stem);
+    stem.strip_prefix("f3d-plugin-").unwrap_or(stem).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::readers::reader;
+
+    #[test]
+    fn diffs_readers() {
+        let before = ReaderRegistry::from(vec![reader("STL", "native", &["stl"], &[])]);
+        let after = ReaderRegistry::from(vec![
+            reader("STL", "native", &["stl"], &[]),
+            reader("GLTF", "assimp", &["gltf", "glb"], &[]),
+            reader("FBX", "assimp", &["fbx"], &[]),
+        ]);
+        let added: Vec<_> = added_readers(&before, after)
+            .into_iter()
+            .map(|reader| reader.name)
+            .collect();
+        assert_eq!(added, ["GLTF", "FBX"]);
+    }
+
+    #[test]
+    fn names_plugins_from_paths() {
+        assert_eq!(plugin_name("assimp"), "assimp");
+        let library = format!("/opt/f3d/lib/{}", library_file_name("occt"));
+        assert_eq!(plugin_name(&library), "occt");
+        assert_eq!(plugin_name("./custom.so"), "custom");
+    }
+
+    #[test]
+    fn finds_manifests() {
+        let dir = std::env::temp_dir().join(format!("f3d-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("assimp.json"), "{}").unwrap();
+        assert!(has_manifest(&dir, "assimp"));
+        assert!(!has_manifest(&dir, "occt"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,539 @@
+//! Procedural meshes ready for [`Scene::add_mesh`](crate::scene::Scene::add_mesh).
+//!
+//! Every primitive is centered on the origin with +Y up, has unit-length
+//! normals, texture coordinates and counter-clockwise faces seen from outside.
+
+use crate::types::F3DMesh;
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+#[derive(Default)]
+struct MeshData {
+    points: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    texcoords: Vec<[f32; 2]>,
+    faces: Vec<Vec<u32>>,
+}
+
+impl MeshData {
+    fn push(&mut self, point: [f32; 3], normal: [f32; 3], texcoord: [f32; 2]) -> u32 {
+        self.points.push(point);
+        self.normals.push(normalize(normal));
+        self.texcoords.push(texcoord);
+        (self.points.len() - 1) as u32
+    }
+
+    fn append(&mut self, other: MeshData, offset: [f32; 3]) {
+        let base = self.points.len() as u32;
+        self.points.extend(
+            other
+                .points
+                .iter()
+                .map(|p| [p[0] + offset[0], p[1] + offset[1], p[2] + offset[2]]),
+        );
+        self.normals.extend(other.normals);
+        self.texcoords.extend(other.texcoords);
+        self.faces.extend(
+            other
+                .faces
+                .into_iter()
+                .map(|face| face.into_iter().map(|index| index + base).collect()),
+        );
+    }
+
+    fn into_mesh(self) -> F3DMesh {
+        F3DMesh::builder()
+            .points(&self.points)
+            .normals(&self.normals)
+            .texcoords(&self.texcoords)
+            .polygons(&self.faces)
+            .build()
+            .expect("primitive meshes are consistent")
+    }
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length == 0.0 {
+        return v;
+    }
+    [v[0] / length, v[1] / length, v[2] / length]
+}
+
+/// Axis-aligned cube with flat-shaded faces.
+pub fn cube(size: f32) -> F3DMesh {
+    let h = size / 2.0;
+    // Normal, then the right and up axes of each face so that right x up = normal.
+    let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ];
+
+    let mut data = MeshData::default();
+    for (normal, right, up) in faces {
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+        let face = corners
+            .iter()
+            .map(|&(u, v)| {
+                let point = [0, 1, 2].map(|i| (normal[i] + u * right[i] + v * up[i]) * h);
+                data.push(point, normal, [(u + 1.0) / 2.0, (v + 1.0) / 2.0])
+            })
+            .collect();
+        data.faces.push(face);
+    }
+    data.into_mesh()
+}
+
+/// Latitude/longitude sphere; `segments` around the Y axis, `rings` from pole to pole.
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> F3DMesh {
+    let segments = segments.max(3);
+    let rings = rings.max(2);
+
+    let mut data = MeshData::default();
+    for ring in 0..=rings {
+        let theta = PI * ring as f32 / rings as f32;
+        for segment in 0..=segments {
+            let phi = TAU * segment as f32 / segments as f32;
+            let normal = [
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                -theta.sin() * phi.sin(),
+            ];
+            let point = normal.map(|v| v * radius);
+            let texcoord = [
+                segment as f32 / segments as f32,
+                1.0 - ring as f32 / rings as f32,
+            ];
+            data.push(point, normal, texcoord);
+        }
+    }
+
+    let row = segments + 1;
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let top_left = ring * row + segment;
+            let top_right = top_left + 1;
+            let bottom_left = top_left + row;
+            let bottom_right = bottom_left + 1;
+            if ring == 0 {
+                data.faces.push(vec![top_left, bottom_left, bottom_right]);
+            } else if ring == rings - 1 {
+                data.faces.push(vec![top_left, bottom_left, top_right]);
+            } else {
+                data.faces
+                    .push(vec![top_left, bottom_left, bottom_right, top_right]);
+            }
+        }
+    }
+    data.into_mesh()
+}
+
+/// Subdivided icosahedron, with `20 * 4^subdivisions` triangles.
+pub fn icosphere(radius: f32, subdivisions: u32) -> F3DMesh {
+    let t = (1.0 + 5f32.sqrt()) / 2.0;
+    let mut points: Vec<[f32; 3]> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .into_iter()
+    .map(normalize)
+    .collect();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, points: &mut Vec<[f32; 3]>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (pa, pb) = (points[a as usize], points[b as usize]);
+                points.push(normalize([0, 1, 2].map(|i| (pa[i] + pb[i]) / 2.0)));
+                (points.len() - 1) as u32
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = midpoint(a, b, &mut points);
+                let bc = midpoint(b, c, &mut points);
+                let ca = midpoint(c, a, &mut points);
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut data = MeshData::default();
+    for normal in points {
+        let texcoord = [
+            0.5 + normal[2].atan2(normal[0]) / TAU,
+            0.5 + normal[1].asin() / PI,
+        ];
+        data.push(normal.map(|v| v * radius), normal, texcoord);
+    }
+    data.faces = triangles.iter().map(|triangle| triangle.to_vec()).collect();
+    data.into_mesh()
+}
+
+/// Flat disc facing `normal_y` (+1 or -1) at height `y`.
+fn disc(radius: f32, y: f32, segments: u32, normal_y: f32) -> MeshData {
+    let mut data = MeshData::default();
+    let normal = [0.0, normal_y, 0.0];
+    let center = data.push([0.0, y, 0.0], normal, [0.5, 0.5]);
+    for segment in 0..segments {
+        let phi = TAU * segment as f32 / segments as f32;
+        let (x, z) = (phi.cos(), -phi.sin());
+        data.push(
+            [x * radius, y, z * radius],
+            normal,
+            [0.5 + x / 2.0, 0.5 - z / 2.0],
+        );
+    }
+    for segment in 0..segments {
+        let current = center + 1 + segment;
+        let next = center + 1 + (segment + 1) % segments;
+        if normal_y > 0.0 {
+            data.faces.push(vec![center, current, next]);
+        } else {
+            data.faces.push(vec![center, next, current]);
+        }
+    }
+    data
+}
+
+fn cylinder_data(radius: f32, height: f32, segments: u32) -> MeshData {
+    let h = height / 2.0;
+    let mut data = MeshData::default();
+    for segment in 0..=segments {
+        let phi = TAU * segment as f32 / segments as f32;
+        let normal = [phi.cos(), 0.0, -phi.sin()];
+        let u = segment as f32 / segments as f32;
+        data.push(
+            [normal[0] * radius, -h, normal[2] * radius],
+            normal,
+            [u, 0.0],
+        );
+        data.push(
+            [normal[0] * radius, h, normal[2] * radius],
+            normal,
+            [u, 1.0],
+        );
+    }
+    for segment in 0..segments {
+        let bottom = segment * 2;
+        data.faces
+            .push(vec![bottom, bottom + 2, bottom + 3, bottom + 1]);
+    }
+    data.append(disc(radius, h, segments, 1.0), [0.0; 3]);
+    data.append(disc(radius, -h, segments, -1.0), [0.0; 3]);
+    data
+}
+
+/// Closed cylinder along Y.
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> F3DMesh {
+    cylinder_data(radius, height, segments.max(3)).into_mesh()
+}
+
+fn cone_data(radius: f32, height: f32, segments: u32) -> MeshData {
+    let h = height / 2.0;
+    let slant = |phi: f32| [height * phi.cos(), radius, -height * phi.sin()];
+
+    let mut data = MeshData::default();
+    for segment in 0..=segments {
+        let phi = TAU * segment as f32 / segments as f32;
+        data.push(
+            [phi.cos() * radius, -h, -phi.sin() * radius],
+            slant(phi),
+            [segment as f32 / segments as f32, 0.0],
+        );
+    }
+    for segment in 0..segments {
+        // One apex per segment so that each side keeps its own normal.
+        let phi = TAU * (segment as f32 + 0.5) / segments as f32;
+        let apex = data.push(
+            [0.0, h, 0.0],
+            slant(phi),
+            [(segment as f32 + 0.5) / segments as f32, 1.0],
+        );
+        data.faces.push(vec![segment, segment + 1, apex]);
+    }
+    data.append(disc(radius, -h, segments, -1.0), [0.0; 3]);
+    data
+}
+
+/// Closed cone along Y, apex up.
+pub fn cone(radius: f32, height: f32, segments: u32) -> F3DMesh {
+    cone_data(radius, height, segments.max(3)).into_mesh()
+}
+
+/// Subdivided rectangle in the XZ plane facing +Y.
+pub fn grid(width: f32, depth: f32, x_segments: u32, z_segments: u32) -> F3DMesh {
+    let x_segments = x_segments.max(1);
+    let z_segments = z_segments.max(1);
+
+    let mut data = MeshData::default();
+    for row in 0..=z_segments {
+        let v = row as f32 / z_segments as f32;
+        for column in 0..=x_segments {
+            let u = column as f32 / x_segments as f32;
+            data.push(
+                [(u - 0.5) * width, 0.0, (0.5 - v) * depth],
+                [0.0, 1.0, 0.0],
+                [u, v],
+            );
+        }
+    }
+
+    let row_length = x_segments + 1;
+    for row in 0..z_segments {
+        for column in 0..x_segments {
+            let corner = row * row_length + column;
+            data.faces.push(vec![
+                corner,
+                corner + 1,
+                corner + row_length + 1,
+                corner + row_length,
+            ]);
+        }
+    }
+    data.into_mesh()
+}
+
+/// Single quad in the XZ plane facing +Y.
+pub fn plane(width: f32, depth: f32) -> F3DMesh {
+    grid(width, depth, 1, 1)
+}
+
+/// Torus around the Y axis.
+pub fn torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> F3DMesh {
+    let major_segments = major_segments.max(3);
+    let minor_segments = minor_segments.max(3);
+
+    let mut data = MeshData::default();
+    for major in 0..=major_segments {
+        let phi = TAU * major as f32 / major_segments as f32;
+        let (cos_phi, sin_phi) = (phi.cos(), -phi.sin());
+        for minor in 0..=minor_segments {
+            let theta = TAU * minor as f32 / minor_segments as f32;
+            let normal = [theta.cos() * cos_phi, theta.sin(), theta.cos() * sin_phi];
+            let ring = major_radius + minor_radius * theta.cos();
+            data.push(
+                [ring * cos_phi, minor_radius * theta.sin(), ring * sin_phi],
+                normal,
+                [
+                    major as f32 / major_segments as f32,
+                    minor as f32 / minor_segments as f32,
+                ],
+            );
+        }
+    }
+
+    let row = minor_segments + 1;
+    for major in 0..major_segments {
+        for minor in 0..minor_segments {
+            let a = major * row + minor;
+            let b = a + row;
+            data.faces.push(vec![a, b, b + 1, a + 1]);
+        }
+    }
+    data.into_mesh()
+}
+
+/// Arrow along +Y starting at the origin: a cylindrical shaft topped by a cone.
+pub fn arrow(length: f32, segments: u32) -> F3DMesh {
+    let segments = segments.max(3);
+    let head_length = length * 0.25;
+    let shaft_length = length - head_length;
+
+    let mut data = MeshData::default();
+    data.append(
+        cylinder_data(length * 0.03, shaft_length, segments),
+        [0.0, shaft_length / 2.0, 0.0],
+    );
+    data.append(
+        cone_data(length * 0.08, head_length, segments),
+        [0.0, shaft_length + head_length / 2.0, 0.0],
+    );
+    data.into_mesh()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(mesh: &F3DMesh, index: u32) -> [f32; 3] {
+        let i = index as usize * 3;
+        [mesh.points()[i], mesh.points()[i + 1], mesh.points()[i + 2]]
+    }
+
+    fn assert_unit_normals(mesh: &F3DMesh) {
+        assert_eq!(mesh.normals().len(), mesh.points().len());
+        assert_eq!(mesh.texcoords().len(), mesh.point_count() * 2);
+        for normal in mesh.normals().chunks(3) {
+            let length =
+                (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+            assert!((length - 1.0).abs() < 1e-5, "normal length {length}");
+        }
+    }
+
+    /// Checks that every face winds counter-clockwise around its vertex normals.
+    fn assert_outward_winding(mesh: &F3DMesh) {
+        let mut offset = 0;
+        for &sides in mesh.face_sides() {
+            let face = &mesh.face_indices()[offset..offset + sides as usize];
+            offset += sides as usize;
+
+            let (a, b, c) = (
+                point(mesh, face[0]),
+                point(mesh, face[1]),
+                point(mesh, face[2]),
+            );
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let cross = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            let n = &mesh.normals()[face[0] as usize * 3..face[0] as usize * 3 + 3];
+            let dot = cross[0] * n[0] + cross[1] * n[1] + cross[2] * n[2];
+            assert!(dot > 0.0, "face {face:?} winds against its normal");
+        }
+    }
+
+    #[test]
+    fn cube_counts() {
+        let mesh = cube(2.0);
+        assert_eq!(mesh.point_count(), 24);
+        assert_eq!(mesh.face_count(), 6);
+        assert!(mesh.face_sides().iter().all(|&sides| sides == 4));
+        assert!(mesh.points().iter().all(|v| v.abs() == 1.0));
+        assert_unit_normals(&mesh);
+        assert_outward_winding(&mesh);
+    }
+
+    #[test]
+    fn uv_sphere_counts() {
+        let mesh = uv_sphere(1.5, 16, 8);
+        assert_eq!(mesh.point_count(), 17 * 9);
+        assert_eq!(mesh.face_count(), 16 * 8);
+        assert_eq!(mesh.face_indices().len(), 16 * 2 * 3 + 16 * 6 * 4);
+        for p in mesh.points().chunks(3) {
+            let radius = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+            assert!((radius - 1.5).abs() < 1e-5);
+        }
+        assert_unit_normals(&mesh);
+        assert_outward_winding(&mesh);
+    }
+
+    #[test]
+    fn icosphere_counts() {
+        for subdivisions in 0..3 {
+            let mesh = icosphere(1.0, subdivisions);
+            assert_eq!(mesh.point_count(), 10 * 4usize.pow(subdivisions) + 2);
+            assert_eq!(mesh.face_count(), 20 * 4usize.pow(subdivisions));
+            assert_unit_normals(&mesh);
+            assert_outward_winding(&mesh);
+        }
+    }
+
+    #[test]
+    fn cylinder_counts() {
+        let mesh = cylinder(0.5, 2.0, 12);
+        assert_eq!(mesh.point_count(), 13 * 2 + 2 * 13);
+        assert_eq!(mesh.face_count(), 12 * 3);
+        assert_unit_normals(&mesh);
+        assert_outward_winding(&mesh);
+    }
+
+    #[test]
+    fn cone_counts() {
+        let mesh = cone(0.5, 1.0, 10);
+        assert_eq!(mesh.point_count(), 11 + 10 + 11);
+        assert_eq!(mesh.face_count(), 20);
+        assert_unit_normals(&mesh);
+        assert_outward_winding(&mesh);
+    }
+
+    #[test]
+    fn grid_counts() {
+        let mesh = grid(4.0, 2.0, 4, 2);
+        assert_eq!(mesh.point_count(), 5 * 3);
+        assert_eq!(mesh.face_count(), 8);
+        assert_unit_normals(&mesh);
+        assert_outward_winding(&mesh);
+
+        let mesh = plane(1.0, 1.0);
+        assert_eq!(mesh.point_count(), 4);
+        assert_eq!(mesh.face_count(), 1);
+    }
+
+    #[test]
+    fn torus_counts() {
+        let mesh = torus(2.0, 0.5, 24, 12);
+        assert_eq!(mesh.point_count(), 25 * 13);
+        assert_eq!(mesh.face_count(), 24 * 12);
+        assert_unit_normals(&mesh);
+        assert_outward_winding(&mesh);
+    }
+
+    #[test]
+    fn arrow_counts() {
+        let mesh = arrow(1.0, 8);
+        let shaft = cylinder(0.03, 0.75, 8);
+        let head = cone(0.08, 0.25, 8);
+        assert_eq!(mesh.point_count(), shaft.point_count() + head.point_count());
+        assert_eq!(mesh.face_count(), shaft.face_count() + head.face_count());
+        let max_y = mesh
+            .points()
+            .chunks(3)
+            .map(|p| p[1])
+            .fold(f32::MIN, f32::max);
+        let min_y = mesh
+            .points()
+            .chunks(3)
+            .map(|p| p[1])
+            .fold(f32::MAX, f32::min);
+        assert!((max_y - 1.0).abs() < 1e-5);
+        assert!(min_y.abs() < 1e-5);
+        assert_unit_normals(&mesh);
+        assert_outward_winding(&mesh);
+    }
+}
@@ -0,0 +1,340 @@
+//! Typed access to the global reader options of libf3d and its plugins.
+//!
+//! libf3d only lists option names and accepts string values, so the types
+//! and defaults of known options come from a table and the current values are
+//! the ones set through this crate. Values set elsewhere, e.g. by a config file
+//! or another copy of this crate, are not seen.
+
+use crate::engine::Engine;
+use crate::error::{Error, Result};
+use crate::options::OptionType;
+use crate::readers::ReaderRegistry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Types and defaults of reader options shipped with libf3d and the official plugins.
+const KNOWN_OPTIONS: &[(&str, OptionType, &str)] = &[
+    ("IGES.angular_deflection", OptionType::Double, "0.5"),
+    ("IGES.linear_deflection", OptionType::Double, "0.1"),
+    ("IGES.read_wire", OptionType::Bool, "true"),
+    ("IGES.relative_deflection", OptionType::Bool, "false"),
+    ("QuakeMDL.skin_index", OptionType::Int, "0"),
+    ("STEP.angular_deflection", OptionType::Double, "0.5"),
+    ("STEP.linear_deflection", OptionType::Double, "0.1"),
+    ("STEP.read_wire", OptionType::Bool, "true"),
+    ("STEP.relative_deflection", OptionType::Bool, "false"),
+    ("VDB.downsampling_factor", OptionType::Double, "1"),
+];
+
+/// Values set through this crate, in libf3d string representation.
+static VALUES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// The map stays consistent even if a holder panicked, so poisoning is ignored.
+fn values() -> MutexGuard<'static, BTreeMap<String, String>> {
+    VALUES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReaderOptionInfo {
+    /// Full name, e.g. `QuakeMDL.skin_index`.
+    pub name: String,
+    pub reader: String,
+    /// Plugin providing the reader, when the reader is currently registered.
+    pub plugin: Option<String>,
+    /// [`OptionType::String`] for options missing from the known table.
+    pub value_type: OptionType,
+    pub default: Option<String>,
+}
+
+impl ReaderOptionInfo {
+    /// Option name without the reader prefix.
+    pub fn option(&self) -> &str {
+        self.name
+            .strip_prefix(&self.reader)
+            .and_then(|name| name.strip_prefix('.'))
+            .unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReaderOptionValue {
+    Bool(bool),
+    Int(i32),
+    Double(f64),
+    String(String),
+}
+
+impl ReaderOptionValue {
+    /// Parses a string representation the way libf3d does for `value_type`.
+    pub fn parse(value_type: OptionType, value: &str) -> Option<Self> {
+        let value = value.trim();
+        Some(match value_type {
+            OptionType::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => ReaderOptionValue::Bool(true),
+                "false" | "0" | "no" | "off" => ReaderOptionValue::Bool(false),
+                _ => return None,
+            },
+            OptionType::Int => ReaderOptionValue::Int(value.parse().ok()?),
+            OptionType::Double => ReaderOptionValue::Double(value.parse().ok()?),
+            _ => ReaderOptionValue::String(value.to_string()),
+        })
+    }
+
+    /// Whether the value can be stored in an option of `value_type`; ints widen to doubles.
+    pub fn fits(&self, value_type: OptionType) -> bool {
+        matches!(
+            (self, value_type),
+            (ReaderOptionValue::Bool(_), OptionType::Bool)
+                | (
+                    ReaderOptionValue::Int(_),
+                    OptionType::Int | OptionType::Double
+                )
+                | (ReaderOptionValue::Double(_), OptionType::Double)
+                | (ReaderOptionValue::String(_), OptionType::String)
+        )
+    }
+}
+
+impl fmt::Display for ReaderOptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReaderOptionValue::Bool(value) => write!(f, "{value}"),
+            ReaderOptionValue::Int(value) => write!(f, "{value}"),
+            ReaderOptionValue::Double(value) => write!(f, "{value}"),
+            ReaderOptionValue::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<bool> for ReaderOptionValue {
+    fn from(value: bool) -> Self {
+        ReaderOptionValue::Bool(value)
+    }
+}
+
+impl From<i32> for ReaderOptionValue {
+    fn from(value: i32) -> Self {
+        ReaderOptionValue::Int(value)
+    }
+}
+
+impl From<f64> for ReaderOptionValue {
+    fn from(value: f64) -> Self {
+        ReaderOptionValue::Double(value)
+    }
+}
+
+impl From<&str> for ReaderOptionValue {
+    fn from(value: &str) -> Self {
+        ReaderOptionValue::String(value.to_string())
+    }
+}
+
+impl From<String> for ReaderOptionValue {
+    fn from(value: String) -> Self {
+        ReaderOptionValue::String(value)
+    }
+}
+
+fn describe(name: &str, registry: &ReaderRegistry) -> ReaderOptionInfo {
+    let reader = name.split_once('.').map_or(name, |(reader, _)| reader);
+    let known = KNOWN_OPTIONS
+        .binary_search_by_key(&name, |&(known, _, _)| known)
+        .ok()
+        .map(|index| KNOWN_OPTIONS[index]);
+    ReaderOptionInfo {
+        name: name.to_string(),
+        reader: reader.to_string(),
+        plugin: registry
+            .get(reader)
+            .map(|reader| reader.plugin_name.clone()),
+        value_type: known.map_or(OptionType::String, |(_, value_type, _)| value_type),
+        default: known.map(|(_, _, default)| default.to_string()),
+    }
+}
+
+/// Every reader option of the registered readers, in libf3d order.
+pub fn descriptors() -> Vec<ReaderOptionInfo> {
+    let registry = ReaderRegistry::current();
+    Engine::get_all_reader_option_names()
+        .iter()
+        .map(|name| describe(name, &registry))
+        .collect()
+}
+
+pub fn descriptor(name: &str) -> Result<ReaderOptionInfo> {
+    if !Engine::get_all_reader_option_names()
+        .iter()
+        .any(|option| option == name)
+    {
+        return Err(Error::InvalidOptionKey(name.to_string()));
+    }
+    Ok(describe(name, &ReaderRegistry::current()))
+}
+
+pub fn by_reader() -> BTreeMap<String, Vec<ReaderOptionInfo>> {
+    let mut readers: BTreeMap<String, Vec<ReaderOptionInfo>> = BTreeMap::new();
+    for info in descriptors() {
+        readers.entry(info.reader.clone()).or_default().push(info);
+    }
+    readers
+}
+
+/// Options grouped by plugin; options of unregistered readers are left out.
+pub fn by_plugin() -> BTreeMap<String, Vec<ReaderOptionInfo>> {
+    let mut plugins: BTreeMap<String, Vec<ReaderOptionInfo>> = BTreeMap::new();
+    for info in descriptors() {
+        if let Some(plugin) = info.plugin.clone() {
+            plugins.entry(plugin).or_default().push(info);
+        }
+    }
+    plugins
+}
+
+/// Checks `value` against the option type without setting it.
+pub fn validate(name: &str, value: &str) -> Result<ReaderOptionValue> {
+    let info = descriptor(name)?;
+    ReaderOptionValue::parse(info.value_type, value).ok_or_else(|| Error::InvalidOptionType {
+        key: name.to_string(),
+        expected: info.value_type.to_string(),
+    })
+}
+
+/// Last value set through this crate, else the known default.
+///
+/// libf3d cannot report reader option values, so a value set elsewhere, e.g. by a
+/// config file, a command line define or another copy of this crate, is not seen
+/// and the value returned may not be the one libf3d uses.
+pub fn get(name: &str) -> Result<Option<ReaderOptionValue>> {
+    let info = descriptor(name)?;
+    let current = values().get(name).cloned();
+    Ok(current
+        .or(info.default)
+        .and_then(|value| ReaderOptionValue::parse(info.value_type, &value)))
+}
+
+pub fn set(name: &str, value: impl Into<ReaderOptionValue>) -> Result<()> {
+    let value = value.into();
+    let info = descriptor(name)?;
+    if !value.fits(info.value_type) {
+        return Err(Error::InvalidOptionType {
+            key: name.to_string(),
+            expected: info.value_type.to_string(),
+        });
+    }
+    Engine::set_reader_option(name, &value.to_string())
+}
+
+pub fn set_string_representation(name: &str, value: &str) -> Result<()> {
+    let value = validate(name, value)?;
+    Engine::set_reader_option(name, &value.to_string())
+}
+
+/// Records a value passed to [`Engine::set_reader_option`].
+pub(crate) fn record(name: &str, value: &str) {
+    values().insert(name.to_string(), value.to_string());
+}
+
+/// Starts a [`ReaderOptionsGuard`].
+pub fn scoped() -> ReaderOptionsGuard {
+    ReaderOptionsGuard {
+        previous: Vec::new(),
+    }
+}
+
+/// Sets reader options for a load and restores their previous values when dropped.
+///
+/// libf3d cannot report the current value of a reader option, so only options
+/// last set through this crate can be restored; others are rejected rather than
+/// reset to a default that may not be their current value.
+#[must_use = "options are restored as soon as the guard is dropped"]
+pub struct ReaderOptionsGuard {
+    previous: Vec<(String, String)>,
+}
+
+impl ReaderOptionsGuard {
+    pub fn set(&mut self, name: &str, value: impl Into<ReaderOptionValue>) -> Result<&mut Self> {
+        let before = self.snapshot(name)?;
+        set(name, value)?;
+        self.previous.push((name.to_string(), before));
+        Ok(self)
+    }
+
+    pub fn set_string_representation(&mut self, name: &str, value: &str) -> Result<&mut Self> {
+        let before = self.snapshot(name)?;
+        set_string_representation(name, value)?;
+        self.previous.push((name.to_string(), before));
+        Ok(self)
+    }
+
+    fn snapshot(&self, name: &str) -> Result<String> {
+        descriptor(name)?;
+        values().get(name).cloned().ok_or_else(|| {
+            Error::InvalidConfig(format!(
+                "reader option {name} was never set through this crate, so it cannot be restored"
+            ))
+        })
+    }
+}
+
+impl Drop for ReaderOptionsGuard {
+    fn drop(&mut self) {
+        for (name, value) in self.previous.drain(..).rev() {
+            let _ = Engine::set_reader_option(&name, &value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_options_are_sorted() {
+        assert!(KNOWN_OPTIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn describes_known_and_unknown_options() {
+        let registry = ReaderRegistry::default();
+        let info = describe("QuakeMDL.skin_index", &registry);
+        assert_eq!(info.reader, "QuakeMDL");
+        assert_eq!(info.option(), "skin_index");
+        assert_eq!(info.value_type, OptionType::Int);
+        assert_eq!(info.default.as_deref(), Some("0"));
+        assert_eq!(info.plugin, None);
+
+        let info = describe("Alembic.custom", &registry);
+        assert_eq!(info.value_type, OptionType::String);
+        assert_eq!(info.default, None);
+    }
+
+    #[test]
+    fn parses_and_checks_values() {
+        assert_eq!(
+            ReaderOptionValue::parse(OptionType::Bool, " Yes"),
+            Some(ReaderOptionValue::Bool(true))
+        );
+        assert_eq!(ReaderOptionValue::parse(OptionType::Int, "1.5"), None);
+        assert_eq!(
+            ReaderOptionValue::parse(OptionType::Double, "1e-2"),
+            Some(ReaderOptionValue::Double(0.01))
+        );
+        assert!(ReaderOptionValue::Int(2).fits(OptionType::Double));
+        assert!(!ReaderOptionValue::Double(2.0).fits(OptionType::Int));
+        assert_eq!(ReaderOptionValue::Bool(false).to_string(), "false");
+    }
+
+    #[test]
+    fn survives_a_poisoned_value_map() {
+        let _ = std::thread::spawn(|| {
+            let _values = values();
+            panic!("poison the map");
+        })
+        .join();
+        assert!(VALUES.is_poisoned());
+        record("Test.poisoned", "1");
+        assert_eq!(values().get("Test.poisoned").map(String::as_str), Some("1"));
+    }
+}
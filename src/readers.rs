@@ -0,0 +1,195 @@
+//! Lookup of the readers available in the linked libf3d.
+
+use crate::engine::{Engine, F3DReaderInfo};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Snapshot of [`Engine::get_readers_info`], including the readers of plugins loaded so far.
+#[derive(Debug, Clone, Default)]
+pub struct ReaderRegistry {
+    readers: Vec<F3DReaderInfo>,
+}
+
+impl ReaderRegistry {
+    /// Queries libf3d; take a new snapshot after loading plugins.
+    pub fn current() -> Self {
+        Self::from(Engine::get_readers_info())
+    }
+
+    pub fn readers(&self) -> &[F3DReaderInfo] {
+        &self.readers
+    }
+
+    pub fn get(&self, name: &str) -> Option<&F3DReaderInfo> {
+        self.readers.iter().find(|reader| reader.name == name)
+    }
+
+    pub fn find_by_extension(&self, extension: &str) -> Vec<&F3DReaderInfo> {
+        self.readers
+            .iter()
+            .filter(|reader| reader.supports_extension(extension))
+            .collect()
+    }
+
+    pub fn find_by_mime(&self, mime_type: &str) -> Vec<&F3DReaderInfo> {
+        self.readers
+            .iter()
+            .filter(|reader| reader.supports_mime_type(mime_type))
+            .collect()
+    }
+
+    /// Matches the end of the file name, so multi-part extensions such as `vtkhdf` or `tar.gz` work.
+    pub fn find_for_path(&self, path: impl AsRef<Path>) -> Vec<&F3DReaderInfo> {
+        let Some(file_name) = path.as_ref().file_name() else {
+            return Vec::new();
+        };
+        let file_name = file_name.to_string_lossy().to_ascii_lowercase();
+        self.readers
+            .iter()
+            .filter(|reader| {
+                reader.extensions.iter().any(|extension| {
+                    let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+                    file_name
+                        .strip_suffix(&extension)
+                        .is_some_and(|stem| stem.ends_with('.'))
+                })
+            })
+            .collect()
+    }
+
+    pub fn scene_readers(&self) -> impl Iterator<Item = &F3DReaderInfo> {
+        self.readers.iter().filter(|reader| reader.has_scene_reader)
+    }
+
+    pub fn geometry_readers(&self) -> impl Iterator<Item = &F3DReaderInfo> {
+        self.readers
+            .iter()
+            .filter(|reader| reader.has_geometry_reader)
+    }
+
+    /// Readers grouped by the plugin providing them, `native` being the built-in one.
+    pub fn by_plugin(&self) -> BTreeMap<&str, Vec<&F3DReaderInfo>> {
+        let mut plugins: BTreeMap<&str, Vec<&F3DReaderInfo>> = BTreeMap::new();
+        for reader in &self.readers {
+            plugins
+                .entry(reader.plugin_name.as_str())
+                .or_default()
+                .push(reader);
+        }
+        plugins
+    }
+
+    pub fn plugins(&self) -> BTreeSet<&str> {
+        self.readers
+            .iter()
+            .map(|reader| reader.plugin_name.as_str())
+            .collect()
+    }
+
+    /// Every supported extension, lowercase and without the leading dot.
+    pub fn extensions(&self) -> BTreeSet<String> {
+        self.readers
+            .iter()
+            .flat_map(|reader| &reader.extensions)
+            .map(|extension| extension.trim_start_matches('.').to_ascii_lowercase())
+            .collect()
+    }
+
+    pub fn mime_types(&self) -> BTreeSet<String> {
+        self.readers
+            .iter()
+            .flat_map(|reader| &reader.mime_types)
+            .map(|mime_type| mime_type.to_ascii_lowercase())
+            .collect()
+    }
+
+    pub fn supports_path(&self, path: impl AsRef<Path>) -> bool {
+        !self.find_for_path(path).is_empty()
+    }
+}
+
+impl From<Vec<F3DReaderInfo>> for ReaderRegistry {
+    fn from(readers: Vec<F3DReaderInfo>) -> Self {
+        Self { readers }
+    }
+}
+
+/// Reader fixture shared by the tests of the modules working on reader lists.
+#[cfg(test)]
+pub(crate) fn reader(
+    name: &str,
+    plugin: &str,
+    extensions: &[&str],
+    mime_types: &[&str],
+) -> F3DReaderInfo {
+    F3DReaderInfo {
+        name: name.to_string(),
+        description: String::new(),
+        extensions: extensions.iter().map(|e| e.to_string()).collect(),
+        mime_types: mime_types.iter().map(|m| m.to_string()).collect(),
+        plugin_name: plugin.to_string(),
+        has_scene_reader: plugin != "native",
+        has_geometry_reader: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> ReaderRegistry {
+        ReaderRegistry::from(vec![
+            reader(
+                "STL",
+                "native",
+                &["stl"],
+                &["model/stl", "model/x.stl-ascii"],
+            ),
+            reader("PLY", "native", &["ply"], &["application/vnd.ply"]),
+            reader("GLTF", "assimp", &["gltf", "glb"], &["model/gltf+json"]),
+            reader("VTKHDF", "hdf", &["vtkhdf", "hdf"], &[]),
+            reader("OBJ", "assimp", &["obj"], &["model/obj"]),
+        ])
+    }
+
+    fn names(readers: Vec<&F3DReaderInfo>) -> Vec<&str> {
+        readers.iter().map(|reader| reader.name.as_str()).collect()
+    }
+
+    #[test]
+    fn finds_by_extension_and_mime() {
+        let registry = registry();
+        assert_eq!(names(registry.find_by_extension(".STL")), ["STL"]);
+        assert_eq!(names(registry.find_by_extension("glb")), ["GLTF"]);
+        assert!(registry.find_by_extension("fbx").is_empty());
+        assert_eq!(names(registry.find_by_mime("Model/OBJ")), ["OBJ"]);
+        assert_eq!(registry.get("PLY").unwrap().extensions, ["ply"]);
+    }
+
+    #[test]
+    fn finds_for_path() {
+        let registry = registry();
+        assert_eq!(names(registry.find_for_path("models/Part.STL")), ["STL"]);
+        assert_eq!(names(registry.find_for_path("scan.vtkhdf")), ["VTKHDF"]);
+        assert!(registry.find_for_path("notstl").is_empty());
+        assert!(!registry.supports_path("archive.zip"));
+    }
+
+    #[test]
+    fn groups_by_capability_and_plugin() {
+        let registry = registry();
+        let scene: Vec<_> = registry.scene_readers().map(|r| r.name.as_str()).collect();
+        assert_eq!(scene, ["GLTF", "VTKHDF", "OBJ"]);
+        assert_eq!(registry.geometry_readers().count(), 5);
+
+        let plugins = registry.by_plugin();
+        assert_eq!(
+            plugins.keys().copied().collect::<Vec<_>>(),
+            ["assimp", "hdf", "native"]
+        );
+        assert_eq!(names(plugins["assimp"].clone()), ["GLTF", "OBJ"]);
+        assert_eq!(registry.plugins().len(), 3);
+        assert!(registry.extensions().contains("glb"));
+        assert!(registry.mime_types().contains("model/stl"));
+    }
+}
@@ -1,57 +1,213 @@
+use crate::engine::{Engine, F3DReaderInfo};
+use crate::error::{Error, Result, check};
+use crate::options::Options;
 use crate::sys::*;
 use crate::types::{F3DLightState, F3DMesh, F3DMeshFFI};
-use std::ffi::{CString, NulError};
+use std::ffi::CString;
+use std::fmt;
+use std::io::Read;
+use std::marker::PhantomData;
 use std::ptr::NonNull;
 
-pub struct Scene {
+/// Restores `scene.force_reader` when dropped, so a panic during a load does not leave
+/// the reader forced.
+struct ForcedReader<'a> {
+    options: Options<'a>,
+    /// Value before the load; `None` once restored.
+    previous: Option<Option<String>>,
+}
+
+impl ForcedReader<'_> {
+    fn restore(&mut self) -> Result<()> {
+        let scene = self.options.scene();
+        match self.previous.take() {
+            Some(Some(previous)) => scene.set_force_reader(previous),
+            Some(None) => scene.unset_force_reader(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ForcedReader<'_> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// Format of in-memory data, used to pick the libf3d reader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatHint {
+    /// Use the reader already forced through `scene.force_reader`. libf3d cannot
+    /// detect the format of in-memory data, so loading fails when none is set.
+    Auto,
+    /// File extension, with or without the leading dot.
+    Extension(String),
+    MimeType(String),
+    /// Reader name as listed by [`Engine::get_readers_info`].
+    Reader(String),
+}
+
+impl FormatHint {
+    /// Treats values containing a `/` as MIME types and anything else as an extension.
+    pub fn guess(mime_or_extension: &str) -> Self {
+        if mime_or_extension.contains('/') {
+            FormatHint::MimeType(mime_or_extension.to_string())
+        } else {
+            FormatHint::Extension(mime_or_extension.to_string())
+        }
+    }
+
+    pub fn matches(&self, reader: &F3DReaderInfo) -> bool {
+        match self {
+            FormatHint::Auto => true,
+            FormatHint::Extension(extension) => reader.supports_extension(extension),
+            FormatHint::MimeType(mime) => reader.supports_mime_type(mime),
+            FormatHint::Reader(name) => reader.name == *name,
+        }
+    }
+
+    /// Name of the first reader matching the hint, or `None` for [`FormatHint::Auto`].
+    pub fn resolve(&self) -> Result<Option<String>> {
+        if *self == FormatHint::Auto {
+            return Ok(None);
+        }
+        Engine::get_readers_info()
+            .into_iter()
+            .find(|reader| self.matches(reader))
+            .map(|reader| Some(reader.name))
+            .ok_or_else(|| Error::UnsupportedFile(self.to_string()))
+    }
+}
+
+impl fmt::Display for FormatHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatHint::Auto => write!(f, "forced reader"),
+            FormatHint::Extension(extension) => {
+                write!(f, "format .{}", extension.trim_start_matches('.'))
+            }
+            FormatHint::MimeType(mime) => write!(f, "format {mime}"),
+            FormatHint::Reader(name) => write!(f, "reader {name}"),
+        }
+    }
+}
+
+impl From<&str> for FormatHint {
+    fn from(mime_or_extension: &str) -> Self {
+        FormatHint::guess(mime_or_extension)
+    }
+}
+
+/// Handle to the scene of an [`Engine`](crate::engine::Engine), valid for the engine's lifetime.
+pub struct Scene<'a> {
     ptr: NonNull<f3d_scene_t>,
+    options: NonNull<f3d_options_t>,
+    _owner: PhantomData<&'a ()>,
 }
 
-impl Scene {
-    pub(crate) unsafe fn from_raw(ptr: *mut f3d_scene_t) -> Self {
+impl<'a> Scene<'a> {
+    pub(crate) unsafe fn from_raw(ptr: *mut f3d_scene_t, options: *mut f3d_options_t) -> Self {
         Self {
             ptr: NonNull::new(ptr).expect("null f3d_scene_t"),
+            options: NonNull::new(options).expect("null f3d_options_t"),
+            _owner: PhantomData,
         }
     }
 
-    pub fn add(&self, path: &str) -> Result<i32, NulError> {
-        let cpath = CString::new(path).expect("path contains interior null bytes");
-        unsafe { Ok(f3d_scene_add(self.ptr.as_ptr(), cpath.as_ptr())) }
+    pub fn add(&self, path: &str) -> Result<()> {
+        let cpath = CString::new(path)?;
+        if !self.supports(path)? {
+            return Err(Error::UnsupportedFile(path.to_string()));
+        }
+        let status = unsafe { f3d_scene_add(self.ptr.as_ptr(), cpath.as_ptr()) };
+        check(status, || Error::LoadFailed(path.to_string()))
     }
 
-    pub fn add_multiple(&self, paths: Vec<&str>) -> Result<i32, NulError> {
-        let cstrings: Vec<CString> = paths
-            .into_iter()
-            .map(|p| CString::new(p).expect("path contains interior null bytes"))
-            .collect();
+    pub fn add_multiple(&self, paths: Vec<&str>) -> Result<()> {
+        let cstrings = paths
+            .iter()
+            .map(|p| CString::new(*p))
+            .collect::<std::result::Result<Vec<CString>, _>>()?;
+        if let Some(path) = paths.iter().find(|p| !self.supports(p).unwrap_or(false)) {
+            return Err(Error::UnsupportedFile(path.to_string()));
+        }
         let cptrs: Vec<*const i8> = cstrings.iter().map(|cs| cs.as_ptr()).collect();
-        unsafe {
-            Ok(f3d_scene_add_multiple(
+        let status = unsafe {
+            f3d_scene_add_multiple(
                 self.ptr.as_ptr(),
                 cptrs.as_ptr() as *mut *const i8,
                 cptrs.len() as usize,
-            ))
-        }
+            )
+        };
+        check(status, || Error::LoadFailed(paths.join(", ")))
     }
 
-    pub fn add_mesh(&self, mesh: &F3DMesh) -> Result<i32, NulError> {
+    pub fn add_mesh(&self, mesh: &F3DMesh) -> Result<()> {
+        mesh.validate()?;
         let f3d_mesh_ffi = mesh.as_f3d_mesh();
-        unsafe {
-            Ok(f3d_scene_add_mesh(
+        let status = unsafe {
+            f3d_scene_add_mesh(
                 self.ptr.as_ptr(),
                 &f3d_mesh_ffi as *const F3DMeshFFI as *const f3d_mesh_t,
-            ))
-        }
+            )
+        };
+        check(status, || Error::LoadFailed("mesh".to_string()))
     }
 
-    pub fn add_buffer(&self, buffer: &mut [u8]) -> Result<i32, NulError> {
-        unsafe {
-            Ok(f3d_scene_add_buffer(
+    pub fn add_buffer(&self, buffer: &mut [u8]) -> Result<()> {
+        let status = unsafe {
+            f3d_scene_add_buffer(
                 self.ptr.as_ptr(),
                 buffer.as_mut_ptr() as *mut std::os::raw::c_void,
                 buffer.len() as usize,
-            ))
-        }
+            )
+        };
+        check(status, || Error::LoadFailed("buffer".to_string()))
+    }
+
+    /// Loads in-memory data, forcing the reader picked from `hint` for the duration of the load.
+    pub fn add_bytes(&self, bytes: &[u8], hint: impl Into<FormatHint>) -> Result<()> {
+        let hint = hint.into();
+        let reader = hint.resolve()?;
+
+        let options = unsafe { Options::from_raw(self.options.as_ptr()) };
+        let previous = options.scene().force_reader()?;
+        let mut forced = match reader {
+            Some(reader) => {
+                let forced = ForcedReader {
+                    options,
+                    previous: Some(previous),
+                };
+                forced.options.scene().set_force_reader(reader)?;
+                Some(forced)
+            }
+            None if previous.is_none() => {
+                return Err(Error::UnsupportedFile(
+                    "in-memory data without a format hint or scene.force_reader".to_string(),
+                ));
+            }
+            None => None,
+        };
+
+        // libf3d only reads the buffer, through a memory stream.
+        let status = unsafe {
+            f3d_scene_add_buffer(
+                self.ptr.as_ptr(),
+                bytes.as_ptr() as *mut std::os::raw::c_void,
+                bytes.len(),
+            )
+        };
+
+        let restored = forced.as_mut().map_or(Ok(()), ForcedReader::restore);
+        check(status, || Error::LoadFailed(format!("buffer ({hint})")))?;
+        restored
+    }
+
+    /// Reads the whole stream into memory, then loads it as [`Scene::add_bytes`] does.
+    pub fn add_reader(&self, mut reader: impl Read, hint: impl Into<FormatHint>) -> Result<()> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        self.add_bytes(&bytes, hint)
     }
 
     pub fn clear(&self) {
@@ -60,13 +216,18 @@ impl Scene {
         }
     }
 
-    pub fn add_light(&self, light_state: F3DLightState) -> Result<i32, NulError>{
-        unsafe {
-            Ok(f3d_scene_add_light(
+    /// Adds a light and returns its index.
+    pub fn add_light(&self, light_state: F3DLightState) -> Result<i32> {
+        let index = unsafe {
+            f3d_scene_add_light(
                 self.ptr.as_ptr(),
                 &light_state as *const F3DLightState as *const f3d_light_state_t,
-            ))
+            )
+        };
+        if index < 0 {
+            return Err(Error::LoadFailed("light".to_string()));
         }
+        Ok(index)
     }
 
     pub fn get_light_count(&self) -> i32 {
@@ -86,18 +247,20 @@ impl Scene {
         }
     }
 
-    pub fn update_light(&self, index: i32, light_state: F3DLightState) -> Result<i32, NulError> {
-        unsafe {
-            Ok(f3d_scene_update_light(
+    pub fn update_light(&self, index: i32, light_state: F3DLightState) -> Result<()> {
+        let status = unsafe {
+            f3d_scene_update_light(
                 self.ptr.as_ptr(),
                 index,
                 &light_state as *const F3DLightState as *const f3d_light_state_t,
-            ))
-        }
+            )
+        };
+        check(status, || Error::LoadFailed(format!("light {index}")))
     }
 
-    pub fn remove_light(&self, index: i32) -> Result<i32, NulError> {
-        unsafe { Ok(f3d_scene_remove_light(self.ptr.as_ptr(), index)) }
+    pub fn remove_light(&self, index: i32) -> Result<()> {
+        let status = unsafe { f3d_scene_remove_light(self.ptr.as_ptr(), index) };
+        check(status, || Error::LoadFailed(format!("light {index}")))
     }
 
     pub fn remove_all_lights(&self) {
@@ -106,9 +269,9 @@ impl Scene {
         }
     }
 
-    pub fn supports(&self, file_path: &str) -> bool {
-        let c_file_path = CString::new(file_path).expect("CString::new failed");
-        unsafe { f3d_scene_supports(self.ptr.as_ptr(), c_file_path.as_ptr()) == 1 }
+    pub fn supports(&self, file_path: &str) -> Result<bool> {
+        let c_file_path = CString::new(file_path)?;
+        unsafe { Ok(f3d_scene_supports(self.ptr.as_ptr(), c_file_path.as_ptr()) == 1) }
     }
 
     pub fn load_animation_time(&self, time_value: f64) {
@@ -134,3 +297,61 @@ impl Scene {
         unsafe { f3d_scene_available_animations(self.ptr.as_ptr()) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::readers::reader;
+
+    #[test]
+    fn guesses_hints() {
+        assert_eq!(
+            FormatHint::guess("model/gltf-binary"),
+            FormatHint::MimeType("model/gltf-binary".to_string())
+        );
+        assert_eq!(
+            FormatHint::guess(".stl"),
+            FormatHint::Extension(".stl".to_string())
+        );
+        assert_eq!(FormatHint::from("ply"), FormatHint::guess("ply"));
+    }
+
+    #[test]
+    fn matches_readers() {
+        let gltf = reader("GLTF", "assimp", &["gltf", "glb"], &["model/gltf-binary"]);
+        assert!(FormatHint::Auto.matches(&gltf));
+        assert!(FormatHint::guess(".GLB").matches(&gltf));
+        assert!(FormatHint::guess("Model/glTF-Binary").matches(&gltf));
+        assert!(FormatHint::Reader("GLTF".to_string()).matches(&gltf));
+        assert!(!FormatHint::guess("stl").matches(&gltf));
+        assert!(!FormatHint::Reader("gltf".to_string()).matches(&gltf));
+    }
+
+    #[test]
+    fn displays_hints() {
+        assert_eq!(FormatHint::Auto.to_string(), "forced reader");
+        assert_eq!(FormatHint::guess(".stl").to_string(), "format .stl");
+        assert_eq!(FormatHint::guess("stl").to_string(), "format .stl");
+        assert_eq!(
+            FormatHint::guess("model/obj").to_string(),
+            "format model/obj"
+        );
+        assert_eq!(
+            FormatHint::Reader("PLY".to_string()).to_string(),
+            "reader PLY"
+        );
+    }
+
+    #[test]
+    fn restores_the_forced_reader_after_a_failed_load() {
+        use crate::engine::{EngineBackend, EngineContext};
+
+        let engine = Engine::new(EngineBackend::None, EngineContext::Internal).unwrap();
+        let err = engine
+            .scene()
+            .add_bytes(b"not a ply file", FormatHint::guess("ply"))
+            .unwrap_err();
+        assert!(matches!(err, Error::LoadFailed(_)));
+        assert_eq!(engine.options().scene().force_reader().unwrap(), None);
+    }
+}
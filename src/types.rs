@@ -1,3 +1,5 @@
+use crate::error::{Error, Result};
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct F3DPoint3 {
@@ -80,6 +82,12 @@ pub struct F3DRatio {
     pub value: f64,
 }
 
+impl F3DRatio {
+    pub fn new(value: f64) -> Self {
+        F3DRatio { value }
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct F3DColor {
@@ -118,22 +126,21 @@ impl F3DTransform2d {
     }
 }
 
-#[repr(C)]
-#[derive(Debug, Copy, Clone)]
+/// Flat list of `value, r, g, b` quadruplets, as stored by libf3d.
+#[derive(Debug, Clone, PartialEq)]
 pub struct F3DColormap {
-    pub data: *mut f64,
-    pub count: usize,
+    pub data: Vec<f64>,
 }
 
 impl F3DColormap {
-    pub fn from_slice(slice: &mut [f64]) -> Self {
+    pub fn from_slice(slice: &[f64]) -> Self {
         Self {
-            data: slice.as_mut_ptr(),
-            count: slice.len(),
+            data: slice.to_vec(),
         }
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct F3DMesh {
     points: Vec<f32>,
     normals: Vec<f32>,
@@ -143,6 +150,99 @@ pub struct F3DMesh {
 }
 
 impl F3DMesh {
+    /// Wraps raw flat arrays without validation.
+    pub fn new(
+        points: Vec<f32>,
+        normals: Vec<f32>,
+        texcoords: Vec<f32>,
+        face_sides: Vec<u32>,
+        face_indices: Vec<u32>,
+    ) -> Self {
+        Self {
+            points,
+            normals,
+            texcoords,
+            face_sides,
+            face_indices,
+        }
+    }
+
+    pub fn builder() -> F3DMeshBuilder {
+        F3DMeshBuilder::default()
+    }
+
+    pub fn points(&self) -> &[f32] {
+        &self.points
+    }
+
+    pub fn normals(&self) -> &[f32] {
+        &self.normals
+    }
+
+    pub fn texcoords(&self) -> &[f32] {
+        &self.texcoords
+    }
+
+    pub fn face_sides(&self) -> &[u32] {
+        &self.face_sides
+    }
+
+    pub fn face_indices(&self) -> &[u32] {
+        &self.face_indices
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.points.len() / 3
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.face_sides.len()
+    }
+
+    /// Checks the array sizes and indices the same way libf3d does before loading a mesh.
+    pub fn validate(&self) -> Result<()> {
+        if !self.points.len().is_multiple_of(3) {
+            return Err(Error::InvalidMesh(format!(
+                "points has {} values, which is not a multiple of 3",
+                self.points.len()
+            )));
+        }
+        if !self.normals.is_empty() && self.normals.len() != self.points.len() {
+            return Err(Error::InvalidMesh(format!(
+                "normals has {} values but points has {}",
+                self.normals.len(),
+                self.points.len()
+            )));
+        }
+        if !self.texcoords.is_empty() && self.texcoords.len() != self.point_count() * 2 {
+            return Err(Error::InvalidMesh(format!(
+                "texcoords has {} values but {} points need {}",
+                self.texcoords.len(),
+                self.point_count(),
+                self.point_count() * 2
+            )));
+        }
+        let sides_sum: usize = self.face_sides.iter().map(|&sides| sides as usize).sum();
+        if sides_sum != self.face_indices.len() {
+            return Err(Error::InvalidMesh(format!(
+                "face_sides sums to {sides_sum} but face_indices has {} values",
+                self.face_indices.len()
+            )));
+        }
+        if let Some((position, index)) = self
+            .face_indices
+            .iter()
+            .enumerate()
+            .find(|&(_, &index)| index as usize >= self.point_count())
+        {
+            return Err(Error::InvalidMesh(format!(
+                "face_indices[{position}] is {index} but there are only {} points",
+                self.point_count()
+            )));
+        }
+        Ok(())
+    }
+
     pub fn as_f3d_mesh(&self) -> F3DMeshFFI {
         F3DMeshFFI {
             points: self.points.as_ptr() as *mut f32,
@@ -159,6 +259,91 @@ impl F3DMesh {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct F3DMeshBuilder {
+    points: Vec<f32>,
+    normals: Vec<f32>,
+    texcoords: Vec<f32>,
+    face_sides: Vec<u32>,
+    face_indices: Vec<u32>,
+}
+
+impl F3DMeshBuilder {
+    pub fn points(mut self, points: &[[f32; 3]]) -> Self {
+        self.points.extend(points.iter().flatten());
+        self
+    }
+
+    /// Flat `x, y, z` coordinates.
+    pub fn points_flat(mut self, points: &[f32]) -> Self {
+        self.points.extend_from_slice(points);
+        self
+    }
+
+    pub fn normals(mut self, normals: &[[f32; 3]]) -> Self {
+        self.normals.extend(normals.iter().flatten());
+        self
+    }
+
+    /// Flat `x, y, z` components.
+    pub fn normals_flat(mut self, normals: &[f32]) -> Self {
+        self.normals.extend_from_slice(normals);
+        self
+    }
+
+    pub fn texcoords(mut self, texcoords: &[[f32; 2]]) -> Self {
+        self.texcoords.extend(texcoords.iter().flatten());
+        self
+    }
+
+    /// Flat `u, v` coordinates.
+    pub fn texcoords_flat(mut self, texcoords: &[f32]) -> Self {
+        self.texcoords.extend_from_slice(texcoords);
+        self
+    }
+
+    pub fn triangles(mut self, triangles: &[[u32; 3]]) -> Self {
+        for triangle in triangles {
+            self.face_sides.push(3);
+            self.face_indices.extend_from_slice(triangle);
+        }
+        self
+    }
+
+    /// Faces with any number of sides, e.g. `&[vec![0, 1, 2, 3]]`.
+    pub fn polygons<I, P>(mut self, polygons: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u32]>,
+    {
+        for polygon in polygons {
+            let polygon = polygon.as_ref();
+            self.face_sides.push(polygon.len() as u32);
+            self.face_indices.extend_from_slice(polygon);
+        }
+        self
+    }
+
+    /// Raw libf3d face layout: a side count per face, followed by all indices.
+    pub fn faces(mut self, face_sides: &[u32], face_indices: &[u32]) -> Self {
+        self.face_sides.extend_from_slice(face_sides);
+        self.face_indices.extend_from_slice(face_indices);
+        self
+    }
+
+    pub fn build(self) -> Result<F3DMesh> {
+        let mesh = F3DMesh::new(
+            self.points,
+            self.normals,
+            self.texcoords,
+            self.face_sides,
+            self.face_indices,
+        );
+        mesh.validate()?;
+        Ok(mesh)
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct F3DMeshFFI {
@@ -215,3 +400,73 @@ impl F3DLightState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle() -> F3DMeshBuilder {
+        F3DMesh::builder()
+            .points(&[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]])
+            .triangles(&[[0, 1, 2]])
+    }
+
+    fn reason(builder: F3DMeshBuilder) -> String {
+        match builder.build() {
+            Err(Error::InvalidMesh(reason)) => reason,
+            other => panic!("expected an invalid mesh, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn builds_valid_meshes() {
+        let mesh = triangle()
+            .normals(&[[0.0, 0.0, 1.0]; 3])
+            .texcoords(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]])
+            .build()
+            .unwrap();
+        assert_eq!(mesh.point_count(), 3);
+        assert_eq!(mesh.face_count(), 1);
+        assert_eq!(mesh.normals().len(), 9);
+
+        let quad = F3DMesh::builder()
+            .points(&[[0.0; 3], [1.0, 0.0, 0.0], [1.0; 3], [0.0, 1.0, 0.0]])
+            .polygons([vec![0, 1, 2, 3]])
+            .faces(&[3], &[0, 2, 3])
+            .build()
+            .unwrap();
+        assert_eq!(quad.face_sides(), &[4, 3]);
+    }
+
+    #[test]
+    fn rejects_mismatched_attribute_lengths() {
+        assert_eq!(
+            reason(triangle().points_flat(&[1.0])),
+            "points has 10 values, which is not a multiple of 3"
+        );
+        assert_eq!(
+            reason(triangle().normals(&[[0.0, 0.0, 1.0]; 2])),
+            "normals has 6 values but points has 9"
+        );
+        assert_eq!(
+            reason(triangle().texcoords(&[[0.0, 0.0]; 4])),
+            "texcoords has 8 values but 3 points need 6"
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_faces() {
+        assert_eq!(
+            reason(triangle().faces(&[4], &[0, 1, 2])),
+            "face_sides sums to 7 but face_indices has 6 values"
+        );
+        assert_eq!(
+            reason(triangle().faces(&[], &[0])),
+            "face_sides sums to 3 but face_indices has 4 values"
+        );
+        assert_eq!(
+            reason(triangle().triangles(&[[0, 1, 3]])),
+            "face_indices[5] is 3 but there are only 3 points"
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use crate::camera::Camera;
+use crate::error::{Error, Result};
 use crate::image::Image;
 use crate::sys::*;
 use crate::types::F3DPoint3;
 use std::ffi::CString;
+use std::marker::PhantomData;
 use std::ptr::NonNull;
 
 #[repr(C)]
@@ -19,14 +21,17 @@ pub enum WindowType {
     Unknown,
 }
 
-pub struct Window {
+/// Handle to the window of an [`Engine`](crate::engine::Engine), valid for the engine's lifetime.
+pub struct Window<'a> {
     ptr: NonNull<f3d_window_t>,
+    _owner: PhantomData<&'a ()>,
 }
 
-impl Window {
+impl<'a> Window<'a> {
     pub(crate) unsafe fn from_raw(ptr: *mut f3d_window_t) -> Self {
         Self {
             ptr: NonNull::new(ptr).expect("null f3d_window_t"),
+            _owner: PhantomData,
         }
     }
 
@@ -38,19 +43,25 @@ impl Window {
         unsafe { f3d_window_is_offscreen(self.ptr.as_ptr()) != 0 }
     }
 
-    pub fn camera(&self) -> Camera {
+    pub fn camera(&self) -> Camera<'a> {
         unsafe { Camera::from_raw(f3d_window_get_camera(self.ptr.as_ptr())) }
     }
 
-    pub fn render(&self) -> bool {
-        unsafe { f3d_window_render(self.ptr.as_ptr()) != 0 }
+    pub fn render(&self) -> Result<()> {
+        if unsafe { f3d_window_render(self.ptr.as_ptr()) } != 0 {
+            Ok(())
+        } else {
+            Err(Error::RenderFailed)
+        }
     }
 
-    pub fn render_to_image(&self, no_background: bool) -> Option<Image> {
+    pub fn render_to_image(&self, no_background: bool) -> Result<Image> {
         let img_ptr = unsafe {
             f3d_window_render_to_image(self.ptr.as_ptr(), if no_background { 1 } else { 0 })
         };
-        NonNull::new(img_ptr).map(|ptr| unsafe { Image::from_raw(ptr.as_ptr()) })
+        NonNull::new(img_ptr)
+            .map(|ptr| unsafe { Image::from_raw(ptr.as_ptr()) })
+            .ok_or(Error::RenderFailed)
     }
 
     pub fn set_size(&self, width: i32, height: i32) {
@@ -79,11 +90,12 @@ impl Window {
         }
     }
 
-    pub fn set_title(&self, title: &str) {
-        let ctitle = CString::new(title).unwrap();
+    pub fn set_title(&self, title: &str) -> Result<()> {
+        let ctitle = CString::new(title)?;
         unsafe {
             f3d_window_set_window_name(self.ptr.as_ptr(), ctitle.as_ptr());
         }
+        Ok(())
     }
 
     pub fn world_from_display(&self, display: F3DPoint3) -> F3DPoint3 {